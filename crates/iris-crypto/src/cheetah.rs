@@ -6,9 +6,11 @@ use alloc::{
     vec::Vec,
 };
 use arrayvec::ArrayVec;
+use crypto_bigint::subtle::{Choice, ConstantTimeEq};
 use iris_ztd::{
     crypto::cheetah::{
-        ch_add, ch_neg, ch_scal_big, trunc_g_order, CheetahPoint, F6lt, A_GEN, G_ORDER,
+        ch_add, ch_multi_scal, ch_neg, ch_scal_base, trunc_g_order, CheetahPoint, F6lt, A_GEN,
+        G_ORDER,
     },
     tip5::hash::hash_varlen,
     Belt, Digest, Hashable, MulMod, U256,
@@ -16,6 +18,7 @@ use iris_ztd::{
 #[cfg(feature = "alloc")]
 use iris_ztd::{Noun, NounDecode, NounEncode};
 use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[cfg_attr(feature = "alloc", derive(NounEncode, NounDecode))]
@@ -44,16 +47,8 @@ impl PublicKey {
         }
 
         // Compute scalar = s*G - c*pubkey
-        // This is equivalent to: scalar = s*G + (-c)*pubkey
-        let sg = match ch_scal_big(&sig.s, &A_GEN) {
-            Ok(pt) => pt,
-            Err(_) => return false,
-        };
-        let c_pk = match ch_scal_big(&sig.c, &self.0) {
-            Ok(pt) => pt,
-            Err(_) => return false,
-        };
-        let scalar = match ch_add(&sg, &ch_neg(&c_pk)) {
+        // This is equivalent to: scalar = s*G + c*(-pubkey)
+        let scalar = match ch_multi_scal(&[(sig.s, A_GEN), (sig.c, ch_neg(&self.0))]) {
             Ok(pt) => pt,
             Err(_) => return false,
         };
@@ -118,6 +113,28 @@ impl PublicKey {
 }
 
 impl PublicKey {
+    /// Verify each of many `(key, message, signature)` triples with [`PublicKey::verify`].
+    ///
+    /// On failure, returns the indices of all invalid signatures.
+    ///
+    /// This is a convenience loop, not a batched check, and costs the same as verifying each
+    /// signature on its own. Signatures carry the challenge rather than the nonce point, so a
+    /// random linear combination over the batch is not possible.
+    #[cfg(feature = "alloc")]
+    pub fn verify_batch(items: &[(PublicKey, Digest, Signature)]) -> Result<(), Vec<usize>> {
+        let invalid = items
+            .iter()
+            .enumerate()
+            .filter(|(_, (pk, m, sig))| !pk.verify(m, sig))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid)
+        }
+    }
+
     pub fn to_be_bytes(&self) -> [u8; 97] {
         let mut data = [0u8; 97];
         data[0] = 0x01; // prefix byte
@@ -277,21 +294,56 @@ impl Hashable for Signature {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Wipe a transcript that contained secret material.
+pub(crate) fn zeroize_belts(belts: &mut [Belt]) {
+    for belt in belts {
+        belt.0.zeroize();
+    }
+}
+
+/// A Cheetah private key.
+///
+/// The scalar is wiped on drop, redacted from `Debug` output, and compared in constant time.
+#[derive(Clone)]
 pub struct PrivateKey(pub U256);
 
+impl core::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("PrivateKey(<redacted>)")
+    }
+}
+
+impl Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl Drop for PrivateKey {
     fn drop(&mut self) {
-        unsafe {
-            core::ptr::write_volatile(&mut self.0, U256::ZERO);
-        }
-        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for PrivateKey {}
+
+impl ConstantTimeEq for PrivateKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
     }
 }
 
+impl Eq for PrivateKey {}
+
 impl PrivateKey {
     pub fn public_key(&self) -> PublicKey {
-        PublicKey(ch_scal_big(&self.0, &A_GEN).unwrap())
+        PublicKey(ch_scal_base(&self.0))
     }
 
     pub fn sign(&self, m: &Digest) -> Signature {
@@ -305,12 +357,17 @@ impl PrivateKey {
             transcript.try_extend_from_slice(&pubkey.x.0).unwrap();
             transcript.try_extend_from_slice(&pubkey.y.0).unwrap();
             transcript.try_extend_from_slice(&m.0).unwrap();
-            self.0.to_le_bytes().chunks(4).for_each(|chunk| {
-                let mut buf = [0u8; 4];
+            let key_bytes = Zeroizing::new(self.0.to_le_bytes());
+            key_bytes.chunks(4).for_each(|chunk| {
+                let mut buf = Zeroizing::new([0u8; 4]);
                 buf[..chunk.len()].copy_from_slice(chunk);
-                transcript.push(Belt(u32::from_le_bytes(buf) as u64));
+                transcript.push(Belt(u32::from_le_bytes(*buf) as u64));
             });
-            trunc_g_order(&hash_varlen(&transcript))
+            let mut hash = hash_varlen(&transcript);
+            zeroize_belts(&mut transcript);
+            let nonce = trunc_g_order(&hash);
+            hash.zeroize();
+            nonce
         };
         nonce
     }
@@ -356,7 +413,7 @@ impl PrivateKey {
     ) -> Signature {
         let chal = {
             // scalar = nonce * G
-            let scalar = ch_scal_big(shared_nonce, &A_GEN).unwrap();
+            let scalar = ch_scal_base(shared_nonce);
             let mut transcript: ArrayVec<Belt, { 6 + 6 + 6 + 6 + 5 }> = ArrayVec::new();
             transcript.try_extend_from_slice(&scalar.x.0).unwrap();
             transcript.try_extend_from_slice(&scalar.y.0).unwrap();
@@ -369,8 +426,8 @@ impl PrivateKey {
             transcript.try_extend_from_slice(&m.0).unwrap();
             trunc_g_order(&hash_varlen(&transcript))
         };
-        let nonce = self.nonce_for(m);
-        let chal_mul = MulMod::mul_mod(&chal, &self.0, &G_ORDER);
+        let nonce = Zeroizing::new(self.nonce_for(m));
+        let chal_mul = Zeroizing::new(MulMod::mul_mod(&chal, &self.0, &G_ORDER));
         let sig = nonce.add_mod(&chal_mul, &G_ORDER);
         Signature { c: chal, s: sig }
     }
@@ -546,6 +603,25 @@ mod tests {
         assert!(pubkey.verify(&digest, &signature));
     }
 
+    #[test]
+    fn test_verify_batch() {
+        let keys = [123u64, 456, 789].map(|k| PrivateKey(U256::from_u64(k)));
+        let mut items = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| {
+                let m = Digest([Belt(i as u64), Belt(2), Belt(3), Belt(4), Belt(5)]);
+                (k.public_key(), m, k.sign(&m))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(PublicKey::verify_batch(&items), Ok(()));
+        assert_eq!(PublicKey::verify_batch(&[]), Ok(()));
+
+        items[0].2.s = items[0].2.s.add_mod(&U256::ONE, &G_ORDER);
+        items[2].0 = keys[1].public_key();
+        assert_eq!(PublicKey::verify_batch(&items), Err(vec![0, 2]));
+    }
+
     #[test]
     fn test_serde() {
         let c_hex = "6f3cd43cd8709f4368aed04cd84292ab1c380cb645aaa7d010669d70375cbe88";
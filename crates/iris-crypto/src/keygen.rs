@@ -0,0 +1,140 @@
+//! Master key generation from entropy, with configurable Argon2 key stretching.
+
+#[cfg(feature = "mnemonic")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "wasm")]
+use alloc::{boxed::Box, format};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "mnemonic")]
+use zeroize::Zeroizing;
+
+#[cfg(feature = "mnemonic")]
+use crate::{derive_master_key, ExtendedKey, Mnemonic};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum KeygenAlgorithm {
+    Argon2d,
+    Argon2id,
+}
+
+/// Key-stretching parameters for [`gen_master_key`].
+///
+/// Keep these next to the mnemonic: the same entropy and salt only reproduce the same key with
+/// the same parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub struct KeygenParams {
+    pub algorithm: KeygenAlgorithm,
+    /// Memory cost in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl KeygenParams {
+    /// Parameters used by `nockchain-wallet keygen`: Argon2d, 768 MiB, 6 iterations, 4 lanes.
+    pub const NOCKCHAIN_CLI: Self = Self {
+        algorithm: KeygenAlgorithm::Argon2d,
+        m_cost: 786_432,
+        t_cost: 6,
+        p_cost: 4,
+    };
+
+    /// Argon2id with 64 MiB and a single lane, for browsers and low-memory devices.
+    pub const LIGHT: Self = Self {
+        algorithm: KeygenAlgorithm::Argon2id,
+        m_cost: 65_536,
+        t_cost: 3,
+        p_cost: 1,
+    };
+
+    /// Minimal parameters for tests. Provides no meaningful protection.
+    pub const TEST: Self = Self {
+        algorithm: KeygenAlgorithm::Argon2id,
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    pub(crate) fn argon2(&self) -> Result<Argon2<'static>, KeygenError> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(KeygenError::Argon2)?;
+        let algorithm = match self.algorithm {
+            KeygenAlgorithm::Argon2d => Algorithm::Argon2d,
+            KeygenAlgorithm::Argon2id => Algorithm::Argon2id,
+        };
+        Ok(Argon2::new(algorithm, Version::V0x13, params))
+    }
+}
+
+impl Default for KeygenParams {
+    fn default() -> Self {
+        Self::NOCKCHAIN_CLI
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeygenError {
+    /// Invalid parameters, or entropy/salt rejected by Argon2 (e.g. salt too short).
+    Argon2(argon2::Error),
+}
+
+impl core::fmt::Display for KeygenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeygenError::Argon2(e) => write!(f, "Argon2 key stretching failed: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for KeygenError {}
+
+/// Output of [`gen_master_key`].
+#[cfg(feature = "mnemonic")]
+#[derive(Clone)]
+pub struct GeneratedKey {
+    pub mnemonic: Zeroizing<String>,
+    /// Parameters the mnemonic was generated with
+    pub params: KeygenParams,
+    pub master_key: ExtendedKey,
+}
+
+#[cfg(feature = "mnemonic")]
+impl core::fmt::Debug for GeneratedKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GeneratedKey")
+            .field("mnemonic", &"<redacted>")
+            .field("params", &self.params)
+            .field("master_key", &self.master_key)
+            .finish()
+    }
+}
+
+/// Generate master key from entropy and salt using Argon2 + BIP39 + SLIP-10
+#[cfg(feature = "mnemonic")]
+pub fn gen_master_key(
+    entropy: &[u8],
+    salt: &[u8],
+    params: &KeygenParams,
+) -> Result<GeneratedKey, KeygenError> {
+    let mut argon_output = Zeroizing::new([0u8; 32]);
+    params
+        .argon2()?
+        .hash_password_into(entropy, salt, &mut argon_output[..])
+        .map_err(KeygenError::Argon2)?;
+
+    argon_output.reverse();
+
+    let mnemonic = Mnemonic::from_entropy(&argon_output[..]).expect("32 bytes is valid entropy");
+    Ok(GeneratedKey {
+        mnemonic: Zeroizing::new(mnemonic.to_string()),
+        params: *params,
+        master_key: derive_master_key(&Zeroizing::new(mnemonic.to_seed(""))[..]),
+    })
+}
@@ -0,0 +1,486 @@
+//! Passphrase-encrypted keystore files.
+//!
+//! A keystore holds a private key or mnemonic encrypted with XChaCha20-Poly1305 under a key
+//! stretched from the passphrase with Argon2 ([`KeygenParams`]). The KDF parameters and the
+//! metadata (derivation path, PKH) are stored in the clear but authenticated, and the whole
+//! keystore serializes to JSON so browser and native wallets can share files:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "kdf": { "algorithm": "Argon2id", "m_cost": 65536, "t_cost": 3, "p_cost": 1, "salt": "…" },
+//!   "cipher": "xchacha20-poly1305",
+//!   "nonce": "…",
+//!   "ciphertext": "…",
+//!   "metadata": { "derivation_path": "m/44'/0'/0/0", "pkh": "…" }
+//! }
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use iris_ztd::crypto::cheetah::G_ORDER;
+use iris_ztd::{Digest, U256};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::cheetah::PrivateKey;
+use crate::{KeygenError, KeygenParams};
+
+/// Current keystore format version.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const KEYSTORE_AAD_TAG: &[u8] = b"iris-keystore";
+
+/// Cheapest KDF accepted when encrypting: 19 MiB and 2 iterations.
+const MIN_M_COST: u32 = 19_456;
+const MIN_T_COST: u32 = 2;
+
+/// Costliest KDF accepted at all, so a crafted keystore cannot exhaust memory or pin the CPU.
+const MAX_M_COST: u32 = 1 << 20;
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
+
+const SECRET_PRIVATE_KEY: u8 = 0;
+const SECRET_MNEMONIC: u8 = 1;
+
+/// The secret stored in a keystore.
+#[derive(Clone)]
+pub enum KeystoreSecret {
+    PrivateKey(PrivateKey),
+    Mnemonic(Zeroizing<String>),
+}
+
+impl core::fmt::Debug for KeystoreSecret {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeystoreSecret::PrivateKey(key) => f.debug_tuple("PrivateKey").field(key).finish(),
+            KeystoreSecret::Mnemonic(_) => f.write_str("Mnemonic(<redacted>)"),
+        }
+    }
+}
+
+impl KeystoreSecret {
+    pub fn mnemonic(mnemonic: &str) -> Self {
+        KeystoreSecret::Mnemonic(Zeroizing::new(mnemonic.into()))
+    }
+
+    /// Tagged plaintext: a type byte followed by the big-endian key or the UTF-8 mnemonic.
+    fn to_plaintext(&self) -> Zeroizing<Vec<u8>> {
+        let mut plaintext = Zeroizing::new(Vec::new());
+        match self {
+            KeystoreSecret::PrivateKey(key) => {
+                plaintext.push(SECRET_PRIVATE_KEY);
+                plaintext.extend_from_slice(&Zeroizing::new(key.to_be_bytes())[..]);
+            }
+            KeystoreSecret::Mnemonic(mnemonic) => {
+                plaintext.push(SECRET_MNEMONIC);
+                plaintext.extend_from_slice(mnemonic.as_bytes());
+            }
+        }
+        plaintext
+    }
+
+    fn from_plaintext(plaintext: &[u8]) -> Option<Self> {
+        match plaintext.split_first()? {
+            (&SECRET_PRIVATE_KEY, key) if key.len() == 32 => {
+                let s = U256::from_be_slice(key);
+                if s == U256::ZERO || s >= G_ORDER {
+                    return None;
+                }
+                Some(KeystoreSecret::PrivateKey(PrivateKey(s)))
+            }
+            (&SECRET_MNEMONIC, mnemonic) => {
+                let mnemonic = core::str::from_utf8(mnemonic).ok()?;
+                Some(KeystoreSecret::Mnemonic(Zeroizing::new(mnemonic.into())))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pkh: Option<Digest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreKdf {
+    #[serde(flatten)]
+    pub params: KeygenParams,
+    #[serde(with = "hex_bytes")]
+    pub salt: [u8; 16],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeystoreCipher {
+    #[serde(rename = "xchacha20-poly1305")]
+    XChaCha20Poly1305,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub kdf: KeystoreKdf,
+    pub cipher: KeystoreCipher,
+    #[serde(with = "hex_bytes")]
+    pub nonce: [u8; 24],
+    #[serde(with = "hex_bytes")]
+    pub ciphertext: Vec<u8>,
+    pub metadata: KeystoreMetadata,
+}
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    UnsupportedVersion(u32),
+    Kdf(KeygenError),
+    /// The KDF parameters are too cheap to protect a new keystore.
+    WeakKdf(KeygenParams),
+    /// The KDF parameters are too costly to run.
+    KdfTooCostly(KeygenParams),
+    /// Wrong passphrase, or the keystore was modified.
+    Decrypt,
+    /// The decrypted payload is not a known secret.
+    InvalidSecret,
+    Json(serde_json::Error),
+}
+
+impl core::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KeystoreError::UnsupportedVersion(v) => write!(f, "Unsupported keystore version {v}"),
+            KeystoreError::Kdf(e) => write!(f, "{e}"),
+            KeystoreError::WeakKdf(p) => write!(
+                f,
+                "Keystore KDF must use at least {MIN_M_COST} KiB and {MIN_T_COST} iterations \
+                 (got {} KiB, {} iterations)",
+                p.m_cost, p.t_cost
+            ),
+            KeystoreError::KdfTooCostly(p) => write!(
+                f,
+                "Keystore KDF must use at most {MAX_M_COST} KiB, {MAX_T_COST} iterations and \
+                 {MAX_P_COST} lanes (got {} KiB, {} iterations, {} lanes)",
+                p.m_cost, p.t_cost, p.p_cost
+            ),
+            KeystoreError::Decrypt => {
+                write!(
+                    f,
+                    "Unable to decrypt keystore: wrong passphrase or corrupted file"
+                )
+            }
+            KeystoreError::InvalidSecret => write!(f, "Keystore contains an unknown secret"),
+            KeystoreError::Json(e) => write!(f, "Invalid keystore JSON: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for KeystoreError {}
+
+impl From<KeygenError> for KeystoreError {
+    fn from(e: KeygenError) -> Self {
+        KeystoreError::Kdf(e)
+    }
+}
+
+impl From<serde_json::Error> for KeystoreError {
+    fn from(e: serde_json::Error) -> Self {
+        KeystoreError::Json(e)
+    }
+}
+
+impl Keystore {
+    /// Encrypt `secret` under `passphrase`, drawing the salt and nonce from `rng`.
+    pub fn encrypt(
+        secret: &KeystoreSecret,
+        passphrase: &[u8],
+        params: &KeygenParams,
+        metadata: KeystoreMetadata,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Self, KeystoreError> {
+        if params.m_cost < MIN_M_COST || params.t_cost < MIN_T_COST {
+            return Err(KeystoreError::WeakKdf(*params));
+        }
+        check_kdf_cost(params)?;
+
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 24];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+
+        let mut keystore = Keystore {
+            version: KEYSTORE_VERSION,
+            kdf: KeystoreKdf {
+                params: *params,
+                salt,
+            },
+            cipher: KeystoreCipher::XChaCha20Poly1305,
+            nonce,
+            ciphertext: Vec::new(),
+            metadata,
+        };
+        let plaintext = secret.to_plaintext();
+        keystore.ciphertext = keystore
+            .cipher(passphrase)?
+            .encrypt(
+                &XNonce::from(keystore.nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &keystore.aad(),
+                },
+            )
+            .expect("plaintext fits in a single message");
+        Ok(keystore)
+    }
+
+    pub fn decrypt(&self, passphrase: &[u8]) -> Result<KeystoreSecret, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        check_kdf_cost(&self.kdf.params)?;
+        let plaintext = Zeroizing::new(
+            self.cipher(passphrase)?
+                .decrypt(
+                    &XNonce::from(self.nonce),
+                    Payload {
+                        msg: &self.ciphertext,
+                        aad: &self.aad(),
+                    },
+                )
+                .map_err(|_| KeystoreError::Decrypt)?,
+        );
+        KeystoreSecret::from_plaintext(&plaintext).ok_or(KeystoreError::InvalidSecret)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("keystore serializes to JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, KeystoreError> {
+        let keystore: Keystore = serde_json::from_str(json)?;
+        if keystore.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(keystore.version));
+        }
+        check_kdf_cost(&keystore.kdf.params)?;
+        Ok(keystore)
+    }
+
+    fn cipher(&self, passphrase: &[u8]) -> Result<XChaCha20Poly1305, KeystoreError> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        self.kdf
+            .params
+            .argon2()?
+            .hash_password_into(passphrase, &self.kdf.salt, &mut key[..])
+            .map_err(KeygenError::Argon2)?;
+        Ok(XChaCha20Poly1305::new((&*key).into()))
+    }
+
+    /// Everything stored in the clear, bound to the ciphertext as associated data.
+    fn aad(&self) -> Vec<u8> {
+        let mut aad = Vec::from(KEYSTORE_AAD_TAG);
+        aad.extend_from_slice(&self.version.to_be_bytes());
+        aad.extend(serde_json::to_vec(&(&self.kdf, self.cipher, &self.metadata)).unwrap());
+        aad
+    }
+}
+
+fn check_kdf_cost(params: &KeygenParams) -> Result<(), KeystoreError> {
+    if params.m_cost > MAX_M_COST || params.t_cost > MAX_T_COST || params.p_cost > MAX_P_COST {
+        return Err(KeystoreError::KdfTooCostly(*params));
+    }
+    Ok(())
+}
+
+mod hex_bytes {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, T: AsRef<[u8]>>(bytes: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D, T>(d: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<u8>>,
+    {
+        let bytes = hex::decode(String::deserialize(d)?).map_err(D::Error::custom)?;
+        T::try_from(bytes).map_err(|_| D::Error::custom("invalid length"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_master_key;
+    use iris_ztd::Hashable;
+    use rand_chacha::rand_core::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::OsRng;
+
+    /// Cheapest parameters a keystore may be encrypted with.
+    const PARAMS: KeygenParams = KeygenParams {
+        algorithm: crate::KeygenAlgorithm::Argon2id,
+        m_cost: MIN_M_COST,
+        t_cost: MIN_T_COST,
+        p_cost: 1,
+    };
+
+    /// Keystore for `derive_master_key(&[4; 64])` under `hunter2`, salt and nonce drawn from
+    /// `ChaCha20Rng::from_seed([7; 32])`. Pins the JSON layout and the associated data.
+    const FIXTURE: &str = r#"{
+  "version": 1,
+  "kdf": {
+    "algorithm": "Argon2id",
+    "m_cost": 19456,
+    "t_cost": 2,
+    "p_cost": 1,
+    "salt": "f400927857aaf64114f561baacb37970"
+  },
+  "cipher": "xchacha20-poly1305",
+  "nonce": "8c79a1dc1476ab573216a4020764bde545c143dbb9609c22",
+  "ciphertext": "f8c0d8f1664356787b3ecc90a59754c78a7c4f07f6de5f5e39d12ff0617db3f84fd8f4e8193321a1be966bbaa54f6c8162",
+  "metadata": {
+    "derivation_path": "m/44'/0'",
+    "pkh": "2zmhvsaTDvQddm8VJKWy992KDRHDQbd1rimvuhpQ6eqw4hH4Qt31pKN"
+  }
+}"#;
+
+    #[test]
+    fn test_keystore_fixture() {
+        let key = derive_master_key(&[4u8; 64]).private_key.unwrap();
+        let keystore = Keystore::from_json(FIXTURE).unwrap();
+        match keystore.decrypt(b"hunter2").unwrap() {
+            KeystoreSecret::PrivateKey(k) => assert_eq!(k.0, key.0),
+            _ => panic!("expected private key"),
+        }
+
+        let metadata = keystore.metadata.clone();
+        let encrypted = Keystore::encrypt(
+            &KeystoreSecret::PrivateKey(key),
+            b"hunter2",
+            &PARAMS,
+            metadata,
+            &mut ChaCha20Rng::from_seed([7u8; 32]),
+        )
+        .unwrap();
+        assert_eq!(encrypted.to_json(), FIXTURE);
+    }
+
+    #[test]
+    fn test_keystore_limits() {
+        let secret = KeystoreSecret::PrivateKey(derive_master_key(&[4u8; 64]).private_key.unwrap());
+        let encrypt = |params: &KeygenParams| {
+            Keystore::encrypt(
+                &secret,
+                b"",
+                params,
+                KeystoreMetadata::default(),
+                &mut OsRng,
+            )
+        };
+        assert!(matches!(
+            encrypt(&KeygenParams::TEST),
+            Err(KeystoreError::WeakKdf(_))
+        ));
+        for params in [
+            KeygenParams {
+                m_cost: MAX_M_COST + 1,
+                ..PARAMS
+            },
+            KeygenParams {
+                t_cost: MAX_T_COST + 1,
+                ..PARAMS
+            },
+            KeygenParams {
+                p_cost: MAX_P_COST + 1,
+                ..PARAMS
+            },
+        ] {
+            assert!(matches!(
+                encrypt(&params),
+                Err(KeystoreError::KdfTooCostly(_))
+            ));
+
+            // Crafted files are rejected before running the KDF
+            let mut keystore = Keystore::from_json(FIXTURE).unwrap();
+            keystore.kdf.params = params;
+            assert!(matches!(
+                keystore.decrypt(b"hunter2"),
+                Err(KeystoreError::KdfTooCostly(_))
+            ));
+            assert!(matches!(
+                Keystore::from_json(&keystore.to_json()),
+                Err(KeystoreError::KdfTooCostly(_))
+            ));
+        }
+
+        // Decrypted private keys must be valid scalars
+        for scalar in [U256::ZERO, G_ORDER, U256::MAX] {
+            let mut plaintext = alloc::vec![SECRET_PRIVATE_KEY];
+            plaintext.extend_from_slice(&scalar.to_be_bytes());
+            assert!(KeystoreSecret::from_plaintext(&plaintext).is_none());
+        }
+        let mut plaintext = alloc::vec![SECRET_PRIVATE_KEY];
+        plaintext.extend_from_slice(&U256::ONE.to_be_bytes());
+        assert!(KeystoreSecret::from_plaintext(&plaintext).is_some());
+    }
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let key = derive_master_key(&[4u8; 64]).private_key.unwrap();
+        let metadata = KeystoreMetadata {
+            derivation_path: Some("m/44'/0'".into()),
+            pkh: Some(key.public_key().hash()),
+        };
+        let keystore = Keystore::encrypt(
+            &KeystoreSecret::PrivateKey(key.clone()),
+            b"hunter2",
+            &PARAMS,
+            metadata.clone(),
+            &mut OsRng,
+        )
+        .unwrap();
+
+        let parsed = Keystore::from_json(&keystore.to_json()).unwrap();
+        assert_eq!(parsed, keystore);
+        assert_eq!(parsed.metadata, metadata);
+        match parsed.decrypt(b"hunter2").unwrap() {
+            KeystoreSecret::PrivateKey(k) => assert_eq!(k.0, key.0),
+            _ => panic!("expected private key"),
+        }
+        assert!(matches!(
+            parsed.decrypt(b"hunter3"),
+            Err(KeystoreError::Decrypt)
+        ));
+
+        // Metadata is authenticated
+        let mut tampered = parsed.clone();
+        tampered.metadata.derivation_path = Some("m/44'/1'".into());
+        assert!(matches!(
+            tampered.decrypt(b"hunter2"),
+            Err(KeystoreError::Decrypt)
+        ));
+
+        let mnemonic = "clutch inmate mango seek attract credit illegal popular term loyal fiber output trumpet lucky garbage merge menu certain dynamic aim trip fantasy master unveil";
+        let keystore = Keystore::encrypt(
+            &KeystoreSecret::mnemonic(mnemonic),
+            b"",
+            &PARAMS,
+            KeystoreMetadata::default(),
+            &mut OsRng,
+        )
+        .unwrap();
+        let secret = keystore.decrypt(b"").unwrap();
+        assert_eq!(alloc::format!("{secret:?}"), "Mnemonic(<redacted>)");
+        match secret {
+            KeystoreSecret::Mnemonic(m) => assert_eq!(m.as_str(), mnemonic),
+            _ => panic!("expected mnemonic"),
+        }
+    }
+}
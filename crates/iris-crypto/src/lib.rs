@@ -2,47 +2,40 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
-#[cfg(feature = "alloc")]
-use alloc::string::{String, ToString};
 
 pub mod cheetah;
+#[cfg(feature = "argon2")]
+pub mod keygen;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "alloc")]
+pub mod message;
+#[cfg(feature = "alloc")]
+pub mod musig;
+#[cfg(feature = "shamir")]
+pub mod shamir;
 pub mod slip10;
 
 pub use cheetah::{PrivateKey, PublicKey, Signature};
-pub use slip10::{derive_master_key, ExtendedKey};
+#[cfg(all(feature = "argon2", feature = "mnemonic"))]
+pub use keygen::{gen_master_key, GeneratedKey};
+#[cfg(feature = "argon2")]
+pub use keygen::{KeygenAlgorithm, KeygenError, KeygenParams};
+#[cfg(feature = "keystore")]
+pub use keystore::{Keystore, KeystoreError, KeystoreMetadata, KeystoreSecret};
+#[cfg(feature = "alloc")]
+pub use message::{message_digest, SignedMessage, SignedMessageError};
+#[cfg(feature = "shamir")]
+pub use shamir::{recover_mnemonic, split_mnemonic, ShamirError, Share};
+pub use slip10::{
+    derive_master_key, DerivationPath, DerivationPathError, DeriveError, ExtendedKey,
+    ExtendedKeyError,
+};
 
 #[cfg(feature = "mnemonic")]
 pub use bip39::Mnemonic;
 pub use iris_ztd::{Digest, Hashable};
 
-#[cfg(feature = "argon2")]
-use argon2::{Algorithm, Argon2, Params, Version};
-
-/// Generate master key from entropy and salt using Argon2 + BIP39 + SLIP-10
-#[cfg(all(feature = "argon2", feature = "mnemonic"))]
-pub fn gen_master_key(entropy: &[u8], salt: &[u8]) -> (String, ExtendedKey) {
-    let mut argon_output = [0u8; 32];
-    let params = Params::new(
-        786_432,  // m_cost: 768 MiB in KiB
-        6,        // t_cost: 6 iterations
-        4,        // p_cost: 4 threads
-        Some(32), // output length
-    )
-    .expect("Invalid Argon2 parameters");
-
-    Argon2::new(Algorithm::Argon2d, Version::V0x13, params)
-        .hash_password_into(entropy, salt, &mut argon_output)
-        .expect("Invalid entropy and/or salt");
-
-    argon_output.reverse();
-
-    let mnemonic = Mnemonic::from_entropy(&argon_output).unwrap();
-    (
-        mnemonic.to_string(),
-        derive_master_key(&mnemonic.to_seed("")),
-    )
-}
-
 #[cfg(test)]
 mod tests {
     extern crate alloc;
@@ -83,8 +76,10 @@ mod tests {
         let entropy = parse_byts_decimal(32, LOG_ENTROPY_DEC);
         let salt = parse_byts_decimal(16, LOG_SALT_DEC);
 
-        let (mnemonic, keypair) = gen_master_key(&entropy, &salt);
-        assert_eq!(mnemonic, LOG_MNEMONIC);
+        let generated = gen_master_key(&entropy, &salt, &KeygenParams::NOCKCHAIN_CLI).unwrap();
+        assert_eq!(generated.mnemonic.as_str(), LOG_MNEMONIC);
+        assert_eq!(generated.params, KeygenParams::default());
+        let keypair = generated.master_key;
 
         // check private key, chain code and pkh
         assert_eq!(
@@ -92,7 +87,7 @@ mod tests {
             "362b4073814e43f427983a83f11efcceb6741082c18f0d64b7e47340ba4485ba"
         );
         assert_eq!(
-            hex::encode(keypair.chain_code),
+            hex::encode(&keypair.chain_code[..]),
             "95b522320f4dfae7486155b9529c582af3d7898ece606a802c43415786ced8d9"
         );
         assert_eq!(
@@ -101,6 +96,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_keygen_params() {
+        let entropy = [5u8; 32];
+        let salt = [6u8; 16];
+
+        let minimal = gen_master_key(&entropy, &salt, &KeygenParams::TEST).unwrap();
+        assert_eq!(
+            minimal.mnemonic,
+            gen_master_key(&entropy, &salt, &KeygenParams::TEST)
+                .unwrap()
+                .mnemonic
+        );
+        let argon2d = KeygenParams {
+            algorithm: KeygenAlgorithm::Argon2d,
+            ..KeygenParams::TEST
+        };
+        assert_ne!(
+            minimal.mnemonic,
+            gen_master_key(&entropy, &salt, &argon2d).unwrap().mnemonic
+        );
+
+        // Parameters round-trip with the mnemonic
+        let json = serde_json::to_string(&minimal.params).unwrap();
+        assert_eq!(
+            serde_json::from_str::<KeygenParams>(&json).unwrap(),
+            KeygenParams::TEST
+        );
+
+        let invalid = KeygenParams {
+            p_cost: 0,
+            ..KeygenParams::TEST
+        };
+        assert!(gen_master_key(&entropy, &salt, &invalid).is_err());
+        assert!(gen_master_key(&entropy, b"short", &KeygenParams::TEST).is_err());
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        use alloc::format;
+
+        let key = derive_master_key(&[7u8; 64]);
+        let private_key = key.private_key.clone().unwrap();
+        let secrets = [
+            hex::encode(private_key.to_be_bytes()),
+            hex::encode_upper(private_key.to_be_bytes()),
+            hex::encode(private_key.0.to_le_bytes()),
+            hex::encode_upper(private_key.0.to_le_bytes()),
+            hex::encode(&key.chain_code[..]),
+            hex::encode_upper(&key.chain_code[..]),
+            format!("{:?}", &key.chain_code[..]),
+        ];
+        for debug in [format!("{private_key:?}"), format!("{key:?}")] {
+            assert!(debug.contains("<redacted>"));
+            for secret in &secrets {
+                assert!(!debug.contains(secret.as_str()));
+            }
+        }
+
+        #[cfg(all(feature = "argon2", feature = "mnemonic"))]
+        {
+            let generated = gen_master_key(&[5u8; 32], &[6u8; 16], &KeygenParams::TEST).unwrap();
+            let debug = format!("{generated:?}");
+            assert!(!debug.contains(generated.mnemonic.split(' ').next().unwrap()));
+        }
+
+        let mut wiped = key.clone();
+        zeroize::Zeroize::zeroize(&mut wiped);
+        assert_eq!(*wiped.chain_code, [0u8; 32]);
+        assert_eq!(wiped.private_key.unwrap().0, iris_ztd::U256::ZERO);
+
+        // Constant-time equality
+        assert_eq!(private_key, key.private_key.clone().unwrap());
+        assert_ne!(
+            private_key,
+            derive_master_key(&[8u8; 64]).private_key.unwrap()
+        );
+    }
+
     #[cfg(feature = "alloc")]
     #[test]
     fn test_ledger_keygen() {
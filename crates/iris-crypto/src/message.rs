@@ -0,0 +1,235 @@
+//! Signing of arbitrary messages, e.g. to prove ownership of an address.
+//!
+//! The signed digest is the tip5 hash of
+//!
+//! ```text
+//! len(MESSAGE_TAG) | MESSAGE_TAG | len(message) | message
+//! ```
+//!
+//! with each byte string packed 4 bytes per belt (little-endian) and each length a single belt
+//! counting bytes. The tag keeps message signatures from ever being valid transaction signatures.
+//!
+//! A [`SignedMessage`] bundles the public key, message and signature into a portable base58check
+//! string:
+//!
+//! ```text
+//! version (1) | public key (97) | c (32, BE) | s (32, BE) | message | checksum (4)
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use iris_ztd::{
+    crypto::cheetah::{CheetahPoint, G_ORDER},
+    tip5::hash::hash_varlen,
+    Belt, Digest, U256,
+};
+use sha2::{Digest as _, Sha256};
+
+use crate::cheetah::{PrivateKey, PublicKey, Signature};
+
+/// Domain separation tag for message signatures.
+pub const MESSAGE_TAG: &[u8] = b"Nockchain Signed Message:\n";
+
+/// Current signed message encoding version.
+pub const SIGNED_MESSAGE_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 1 + 97 + 32 + 32;
+const CHECKSUM_LEN: usize = 4;
+
+/// The digest signed for `message`.
+pub fn message_digest(message: &[u8]) -> Digest {
+    let mut transcript = Vec::with_capacity(2 + (MESSAGE_TAG.len() + message.len()) / 4 + 2);
+    transcript.push(Belt(MESSAGE_TAG.len() as u64));
+    transcript.extend(Belt::from_bytes(MESSAGE_TAG));
+    transcript.push(Belt(message.len() as u64));
+    transcript.extend(Belt::from_bytes(message));
+    Digest(hash_varlen(&transcript).map(Belt))
+}
+
+impl PrivateKey {
+    pub fn sign_message(&self, message: &[u8]) -> SignedMessage {
+        SignedMessage {
+            public_key: self.public_key(),
+            message: message.to_vec(),
+            signature: self.sign(&message_digest(message)),
+        }
+    }
+}
+
+impl PublicKey {
+    pub fn verify_message(&self, message: &[u8], sig: &Signature) -> bool {
+        self.verify(&message_digest(message), sig)
+    }
+}
+
+#[derive(Debug)]
+pub enum SignedMessageError {
+    Base58Decode(bs58::decode::Error),
+    InvalidLength(usize),
+    InvalidChecksum,
+    UnsupportedVersion(u8),
+    InvalidPublicKey,
+    InvalidSignature,
+}
+
+impl core::fmt::Display for SignedMessageError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SignedMessageError::Base58Decode(e) => write!(f, "Invalid base58: {e}"),
+            SignedMessageError::InvalidLength(len) => {
+                write!(f, "Invalid signed message length {len}")
+            }
+            SignedMessageError::InvalidChecksum => write!(f, "Invalid signed message checksum"),
+            SignedMessageError::UnsupportedVersion(v) => {
+                write!(f, "Unsupported signed message version {v}")
+            }
+            SignedMessageError::InvalidPublicKey => write!(f, "Invalid public key"),
+            SignedMessageError::InvalidSignature => write!(f, "Invalid signature"),
+        }
+    }
+}
+
+impl core::error::Error for SignedMessageError {}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = Sha256::digest(Sha256::digest(payload));
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&hash[..CHECKSUM_LEN]);
+    checksum
+}
+
+/// A message together with its signer and signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedMessage {
+    pub public_key: PublicKey,
+    pub message: Vec<u8>,
+    pub signature: Signature,
+}
+
+impl SignedMessage {
+    pub fn verify(&self) -> bool {
+        self.public_key
+            .verify_message(&self.message, &self.signature)
+    }
+
+    /// The message as text, if it is valid UTF-8.
+    pub fn message_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.message).ok()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.message.len() + CHECKSUM_LEN);
+        bytes.push(SIGNED_MESSAGE_VERSION);
+        bytes.extend_from_slice(&self.public_key.to_be_bytes());
+        bytes.extend_from_slice(&self.signature.c.to_be_bytes());
+        bytes.extend_from_slice(&self.signature.s.to_be_bytes());
+        bytes.extend_from_slice(&self.message);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+
+    /// Parse a signed message. The signature is not verified; see [`SignedMessage::verify`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignedMessageError> {
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(SignedMessageError::InvalidLength(bytes.len()));
+        }
+        let (payload, check) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(payload) != check {
+            return Err(SignedMessageError::InvalidChecksum);
+        }
+        if payload[0] != SIGNED_MESSAGE_VERSION {
+            return Err(SignedMessageError::UnsupportedVersion(payload[0]));
+        }
+
+        let public_key = CheetahPoint::from_bytes(&payload[1..98])
+            .map_err(|_| SignedMessageError::InvalidPublicKey)?;
+        let c = U256::from_be_slice(&payload[98..130]);
+        let s = U256::from_be_slice(&payload[130..HEADER_LEN]);
+        if c >= G_ORDER || s >= G_ORDER {
+            return Err(SignedMessageError::InvalidSignature);
+        }
+
+        Ok(SignedMessage {
+            public_key: PublicKey(public_key),
+            message: payload[HEADER_LEN..].to_vec(),
+            signature: Signature { c, s },
+        })
+    }
+
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.to_bytes()).into_string()
+    }
+
+    pub fn from_base58(s: &str) -> Result<Self, SignedMessageError> {
+        let bytes = bs58::decode(s.trim())
+            .into_vec()
+            .map_err(SignedMessageError::Base58Decode)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl core::fmt::Display for SignedMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.to_base58())
+    }
+}
+
+impl core::str::FromStr for SignedMessage {
+    type Err = SignedMessageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_base58(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_master_key;
+    use alloc::string::ToString;
+    use iris_ztd::{Hashable, NounEncode};
+
+    #[test]
+    fn test_sign_message() {
+        let key = derive_master_key(&[5u8; 64]).private_key.unwrap();
+        let message = b"I own this address";
+        let signed = key.sign_message(message);
+        assert!(signed.verify());
+        assert_eq!(signed.message_str(), Some("I own this address"));
+
+        let parsed: SignedMessage = signed.to_string().parse().unwrap();
+        assert_eq!(parsed, signed);
+        assert!(parsed.verify());
+        assert_eq!(parsed.public_key.hash(), key.public_key().hash());
+
+        // Length prefix and tag separate messages and digests
+        assert_ne!(message_digest(b"ab"), message_digest(b"ab\0"));
+        assert_ne!(
+            message_digest(message),
+            Belt::from_bytes(message).to_noun().hash()
+        );
+
+        // Wrong message or key
+        assert!(!key
+            .public_key()
+            .verify_message(b"I own that address", &signed.signature));
+        let mut forged = signed.clone();
+        forged.public_key = derive_master_key(&[6u8; 64]).public_key;
+        assert!(!SignedMessage::from_base58(&forged.to_base58())
+            .unwrap()
+            .verify());
+
+        // Corrupted blob
+        let mut bytes = signed.to_bytes();
+        bytes[HEADER_LEN] ^= 1;
+        assert!(matches!(
+            SignedMessage::from_bytes(&bytes),
+            Err(SignedMessageError::InvalidChecksum)
+        ));
+        assert!(matches!(
+            SignedMessage::from_bytes(&bytes[..10]),
+            Err(SignedMessageError::InvalidLength(10))
+        ));
+    }
+}
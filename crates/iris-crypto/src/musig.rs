@@ -0,0 +1,552 @@
+//! MuSig-style multi-party Schnorr signing over Cheetah.
+//!
+//! Unlike [`PrivateKey::sign_multi`], which simply adds up keys and deterministic nonces, this
+//! protocol is safe against adversarial co-signers:
+//!
+//! * Keys are aggregated as `X = Σ aᵢ·Pᵢ` with coefficients `aᵢ = H(L, Pᵢ)` bound to the whole
+//!   key list `L`, so a co-signer cannot choose their key to cancel out others (rogue-key attack).
+//! * Nonces are committed to before any of them are revealed, so a co-signer cannot choose their
+//!   nonce based on the others.
+//!
+//! Each signer runs a [`MusigSession`] and exchanges three rounds of messages with all other
+//! signers: [`NonceCommitment`], [`NonceReveal`] and [`PartialSignature`]. The final
+//! [`Signature`] verifies against [`KeyAggregate::public_key`] like any other signature.
+//!
+//! ```
+//! # use iris_crypto::{musig::*, PrivateKey};
+//! # use iris_ztd::{Belt, Digest, U256};
+//! let keys = [PrivateKey(U256::from_u64(123)), PrivateKey(U256::from_u64(456))];
+//! let agg = KeyAggregate::new(&keys.iter().map(|k| k.public_key()).collect::<Vec<_>>()).unwrap();
+//! let m = Digest([Belt(1), Belt(2), Belt(3), Belt(4), Belt(5)]);
+//!
+//! // Entropy must be fresh for every session
+//! let mut sessions = keys
+//!     .iter()
+//!     .zip([[1u8; 32], [2u8; 32]])
+//!     .map(|(k, entropy)| MusigSession::new(k, agg.clone(), m, &entropy).unwrap())
+//!     .collect::<Vec<_>>();
+//!
+//! let commitments: Vec<_> = sessions.iter().map(|s| s.commitment()).collect();
+//! for s in &mut sessions {
+//!     for c in &commitments {
+//!         s.add_commitment(c).unwrap();
+//!     }
+//! }
+//! let reveals: Vec<_> = sessions.iter().map(|s| s.reveal().unwrap()).collect();
+//! for s in &mut sessions {
+//!     for r in &reveals {
+//!         s.add_reveal(r).unwrap();
+//!     }
+//! }
+//! let partials: Vec<_> = sessions.iter_mut().map(|s| s.partial_sign().unwrap()).collect();
+//! for s in &mut sessions {
+//!     for p in &partials {
+//!         s.add_partial(p).unwrap();
+//!     }
+//! }
+//! let sig = sessions[0].finalize().unwrap();
+//! assert!(agg.public_key().verify(&m, &sig));
+//! ```
+//!
+//! Nonces are derived from the private key, the message, the aggregate key and caller-supplied
+//! entropy. The entropy must never be reused: signing the same message twice with the same
+//! entropy but different co-signer nonces leaks the private key.
+
+use alloc::vec::Vec;
+use arrayvec::ArrayVec;
+use iris_ztd::{
+    crypto::cheetah::{ch_add, ch_scal_base, ch_scal_big, trunc_g_order, CheetahPoint, G_ORDER},
+    tip5::hash::hash_varlen,
+    Belt, Digest, MulMod, U256,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::cheetah::{zeroize_belts, PrivateKey, PublicKey, Signature};
+
+/// Domain separation tag, packed into a single belt.
+const fn tag(s: &[u8; 7]) -> Belt {
+    let mut out = 0u64;
+    let mut i = 0;
+    while i < 7 {
+        out |= (s[i] as u64) << (i * 8);
+        i += 1;
+    }
+    Belt(out)
+}
+
+const TAG_KEY_LIST: Belt = tag(b"mu-list");
+const TAG_COEFFICIENT: Belt = tag(b"mu-coef");
+const TAG_NONCE: Belt = tag(b"mu-nonc");
+const TAG_COMMITMENT: Belt = tag(b"mu-comm");
+
+fn point_belts(p: &CheetahPoint) -> impl Iterator<Item = Belt> + '_ {
+    p.x.0.iter().chain(p.y.0.iter()).copied()
+}
+
+/// Append `n` to `transcript` as 32-bit limbs, without leaving copies of it behind.
+fn push_u256_belts<const N: usize>(transcript: &mut ArrayVec<Belt, N>, n: &U256) {
+    let bytes = Zeroizing::new(n.to_le_bytes());
+    for chunk in bytes.chunks(4) {
+        let mut buf = Zeroizing::new([0u8; 4]);
+        buf[..chunk.len()].copy_from_slice(chunk);
+        transcript.push(Belt(u32::from_le_bytes(*buf) as u64));
+    }
+}
+
+/// The Schnorr challenge, computed exactly like [`PublicKey::verify`] does.
+fn challenge(nonce_point: &CheetahPoint, pubkey: &PublicKey, m: &Digest) -> U256 {
+    let mut transcript: ArrayVec<Belt, { 6 + 6 + 6 + 6 + 5 }> = ArrayVec::new();
+    transcript.extend(point_belts(nonce_point));
+    transcript.extend(point_belts(&pubkey.0));
+    transcript.try_extend_from_slice(&m.0).unwrap();
+    trunc_g_order(&hash_varlen(&transcript))
+}
+
+fn commit(nonce_point: &PublicKey) -> Digest {
+    let mut transcript: ArrayVec<Belt, { 1 + 6 + 6 }> = ArrayVec::new();
+    transcript.push(TAG_COMMITMENT);
+    transcript.extend(point_belts(&nonce_point.0));
+    Digest(hash_varlen(&transcript).map(Belt))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MusigError {
+    /// No keys were given, or the same key was given twice.
+    InvalidKeySet,
+    /// The message comes from a key that is not part of the aggregate.
+    UnknownSigner(PublicKey),
+    /// The message was received in the wrong round.
+    WrongRound,
+    /// The revealed nonce does not match the signer's commitment.
+    CommitmentMismatch(PublicKey),
+    /// The partial signature does not verify against the signer's key and nonce.
+    InvalidPartialSignature(PublicKey),
+    /// A point operation failed (e.g. the nonces add up to the identity).
+    InvalidPoint,
+}
+
+impl core::fmt::Display for MusigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MusigError::InvalidKeySet => write!(f, "Key set is empty or contains duplicates"),
+            MusigError::UnknownSigner(pk) => write!(f, "Unknown signer {pk}"),
+            MusigError::WrongRound => write!(f, "Message is not valid in the current round"),
+            MusigError::CommitmentMismatch(pk) => {
+                write!(f, "Nonce of {pk} does not match its commitment")
+            }
+            MusigError::InvalidPartialSignature(pk) => {
+                write!(f, "Invalid partial signature from {pk}")
+            }
+            MusigError::InvalidPoint => write!(f, "Invalid curve point"),
+        }
+    }
+}
+
+impl core::error::Error for MusigError {}
+
+/// A set of public keys aggregated with rogue-key resistant coefficients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyAggregate {
+    /// Sorted signer keys, with their coefficients
+    signers: Vec<(PublicKey, U256)>,
+    public_key: PublicKey,
+}
+
+impl KeyAggregate {
+    /// Aggregate `pubkeys`. The order of keys does not matter.
+    pub fn new(pubkeys: &[PublicKey]) -> Result<Self, MusigError> {
+        let mut keys = pubkeys.to_vec();
+        keys.sort();
+        keys.dedup();
+        if keys.is_empty() || keys.len() != pubkeys.len() {
+            return Err(MusigError::InvalidKeySet);
+        }
+
+        let mut transcript = Vec::with_capacity(1 + 12 * keys.len());
+        transcript.push(TAG_KEY_LIST);
+        for key in &keys {
+            transcript.extend(point_belts(&key.0));
+        }
+        let key_list = hash_varlen(&transcript).map(Belt);
+
+        let mut signers = Vec::with_capacity(keys.len());
+        let mut public_key = CheetahPoint::identity();
+        for key in keys {
+            let mut transcript: ArrayVec<Belt, { 1 + 5 + 6 + 6 }> = ArrayVec::new();
+            transcript.push(TAG_COEFFICIENT);
+            transcript.try_extend_from_slice(&key_list).unwrap();
+            transcript.extend(point_belts(&key.0));
+            let coefficient = trunc_g_order(&hash_varlen(&transcript));
+
+            let term = ch_scal_big(&coefficient, &key.0).map_err(|_| MusigError::InvalidPoint)?;
+            public_key = ch_add(&public_key, &term).map_err(|_| MusigError::InvalidPoint)?;
+            signers.push((key, coefficient));
+        }
+
+        Ok(Self {
+            signers,
+            public_key: PublicKey(public_key),
+        })
+    }
+
+    /// The aggregate public key that final signatures verify against.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// The signer keys, sorted.
+    pub fn signers(&self) -> impl Iterator<Item = &PublicKey> {
+        self.signers.iter().map(|(k, _)| k)
+    }
+
+    pub fn coefficient(&self, signer: &PublicKey) -> Option<U256> {
+        self.index_of(signer).map(|i| self.signers[i].1)
+    }
+
+    fn index_of(&self, signer: &PublicKey) -> Option<usize> {
+        self.signers.binary_search_by(|(k, _)| k.cmp(signer)).ok()
+    }
+}
+
+/// Round 1: commitment to a signer's nonce point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub signer: PublicKey,
+    pub commitment: Digest,
+}
+
+/// Round 2: a signer's nonce point, checked against its commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceReveal {
+    pub signer: PublicKey,
+    pub nonce: PublicKey,
+}
+
+/// Round 3: a signer's share of the final signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+    pub signer: PublicKey,
+    pub s: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusigRound {
+    /// Collecting nonce commitments
+    Commit,
+    /// Collecting nonce reveals
+    Reveal,
+    /// Collecting partial signatures
+    Sign,
+    /// All partial signatures collected
+    Done,
+}
+
+/// One signer's view of a signing session.
+///
+/// Messages from all signers, including our own, must be fed back in with the `add_*`
+/// methods. Each round's outgoing message becomes available once the previous round is
+/// complete.
+pub struct MusigSession {
+    key: PrivateKey,
+    agg: KeyAggregate,
+    message: Digest,
+    /// Secret nonce; taken when signing so it can never be used twice
+    nonce: Option<PrivateKey>,
+    nonce_point: PublicKey,
+    commitments: Vec<Option<Digest>>,
+    reveals: Vec<Option<PublicKey>>,
+    partials: Vec<Option<U256>>,
+    /// Aggregate nonce point and challenge, once all nonces are revealed
+    challenge: Option<(CheetahPoint, U256)>,
+}
+
+impl MusigSession {
+    /// Start a session for signing `message` with `key`, which must be part of `agg`.
+    ///
+    /// `entropy` must be fresh randomness, never reused across sessions.
+    pub fn new(
+        key: &PrivateKey,
+        agg: KeyAggregate,
+        message: Digest,
+        entropy: &[u8; 32],
+    ) -> Result<Self, MusigError> {
+        let signer = key.public_key();
+        agg.index_of(&signer)
+            .ok_or(MusigError::UnknownSigner(signer))?;
+
+        let nonce = {
+            let mut transcript: ArrayVec<Belt, { 1 + 8 + 6 + 6 + 5 + 8 }> = ArrayVec::new();
+            transcript.push(TAG_NONCE);
+            push_u256_belts(&mut transcript, &key.0);
+            transcript.extend(point_belts(&agg.public_key.0));
+            transcript.try_extend_from_slice(&message.0).unwrap();
+            push_u256_belts(
+                &mut transcript,
+                &Zeroizing::new(U256::from_le_slice(entropy)),
+            );
+            let mut hash = hash_varlen(&transcript);
+            zeroize_belts(&mut transcript);
+            let nonce = PrivateKey(trunc_g_order(&hash));
+            hash.zeroize();
+            nonce
+        };
+        if nonce.0 == U256::ZERO {
+            return Err(MusigError::InvalidPoint);
+        }
+        let nonce_point = nonce.public_key();
+
+        let n = agg.signers.len();
+        Ok(Self {
+            key: key.clone(),
+            agg,
+            message,
+            nonce: Some(nonce),
+            nonce_point,
+            commitments: alloc::vec![None; n],
+            reveals: alloc::vec![None; n],
+            partials: alloc::vec![None; n],
+            challenge: None,
+        })
+    }
+
+    pub fn round(&self) -> MusigRound {
+        if self.commitments.iter().any(Option::is_none) {
+            MusigRound::Commit
+        } else if self.challenge.is_none() {
+            MusigRound::Reveal
+        } else if self.partials.iter().any(Option::is_none) {
+            MusigRound::Sign
+        } else {
+            MusigRound::Done
+        }
+    }
+
+    pub fn key_aggregate(&self) -> &KeyAggregate {
+        &self.agg
+    }
+
+    fn signer_index(&self, signer: &PublicKey) -> Result<usize, MusigError> {
+        self.agg
+            .index_of(signer)
+            .ok_or(MusigError::UnknownSigner(*signer))
+    }
+
+    /// Our round 1 message.
+    pub fn commitment(&self) -> NonceCommitment {
+        NonceCommitment {
+            signer: self.key.public_key(),
+            commitment: commit(&self.nonce_point),
+        }
+    }
+
+    pub fn add_commitment(&mut self, msg: &NonceCommitment) -> Result<(), MusigError> {
+        if self.round() != MusigRound::Commit {
+            return Err(MusigError::WrongRound);
+        }
+        let i = self.signer_index(&msg.signer)?;
+        match self.commitments[i] {
+            // Commitments may not be changed once received
+            Some(c) if c != msg.commitment => Err(MusigError::CommitmentMismatch(msg.signer)),
+            _ => {
+                self.commitments[i] = Some(msg.commitment);
+                Ok(())
+            }
+        }
+    }
+
+    /// Our round 2 message, available once all commitments are in.
+    pub fn reveal(&self) -> Result<NonceReveal, MusigError> {
+        if self.round() == MusigRound::Commit {
+            return Err(MusigError::WrongRound);
+        }
+        Ok(NonceReveal {
+            signer: self.key.public_key(),
+            nonce: self.nonce_point,
+        })
+    }
+
+    pub fn add_reveal(&mut self, msg: &NonceReveal) -> Result<(), MusigError> {
+        if self.round() != MusigRound::Reveal {
+            return Err(MusigError::WrongRound);
+        }
+        let i = self.signer_index(&msg.signer)?;
+        if self.commitments[i] != Some(commit(&msg.nonce)) {
+            return Err(MusigError::CommitmentMismatch(msg.signer));
+        }
+        self.reveals[i] = Some(msg.nonce);
+
+        if self.reveals.iter().all(Option::is_some) {
+            let r = self
+                .reveals
+                .iter()
+                .flatten()
+                .try_fold(CheetahPoint::identity(), |acc, p| ch_add(&acc, &p.0))
+                .map_err(|_| MusigError::InvalidPoint)?;
+            if r.inf {
+                return Err(MusigError::InvalidPoint);
+            }
+            let c = challenge(&r, &self.agg.public_key, &self.message);
+            self.challenge = Some((r, c));
+        }
+        Ok(())
+    }
+
+    /// Our round 3 message, available once all nonces are revealed.
+    ///
+    /// Can only be called once per session; the secret nonce is destroyed afterwards.
+    pub fn partial_sign(&mut self) -> Result<PartialSignature, MusigError> {
+        let (Some((_, c)), MusigRound::Sign) = (self.challenge, self.round()) else {
+            return Err(MusigError::WrongRound);
+        };
+        let nonce = self.nonce.take().ok_or(MusigError::WrongRound)?;
+        let signer = self.key.public_key();
+        let a = self.agg.coefficient(&signer).expect("checked in new");
+
+        // s = k + c·a·x
+        let cax = Zeroizing::new(MulMod::mul_mod(
+            &MulMod::mul_mod(&c, &a, &G_ORDER),
+            &self.key.0,
+            &G_ORDER,
+        ));
+        Ok(PartialSignature {
+            signer,
+            s: nonce.0.add_mod(&cax, &G_ORDER),
+        })
+    }
+
+    /// Add and verify a partial signature: `s·G == R + c·a·P`.
+    pub fn add_partial(&mut self, msg: &PartialSignature) -> Result<(), MusigError> {
+        let (Some((_, c)), MusigRound::Sign) = (self.challenge, self.round()) else {
+            return Err(MusigError::WrongRound);
+        };
+        let i = self.signer_index(&msg.signer)?;
+        let (_, a) = self.agg.signers[i];
+        let r = self.reveals[i].expect("all reveals received");
+
+        let invalid = MusigError::InvalidPartialSignature(msg.signer);
+        if msg.s >= G_ORDER {
+            return Err(invalid);
+        }
+        let lhs = ch_scal_base(&msg.s);
+        let ca = MulMod::mul_mod(&c, &a, &G_ORDER);
+        let rhs = ch_scal_big(&ca, &msg.signer.0)
+            .and_then(|cap| ch_add(&r.0, &cap))
+            .map_err(|_| invalid.clone())?;
+        if lhs != rhs {
+            return Err(invalid);
+        }
+
+        self.partials[i] = Some(msg.s);
+        Ok(())
+    }
+
+    /// The final signature, valid for the aggregate public key.
+    pub fn finalize(&self) -> Result<Signature, MusigError> {
+        let (Some((_, c)), MusigRound::Done) = (self.challenge, self.round()) else {
+            return Err(MusigError::WrongRound);
+        };
+        let s = self
+            .partials
+            .iter()
+            .flatten()
+            .fold(U256::ZERO, |acc, s| acc.add_mod(s, &G_ORDER));
+        Ok(Signature { c, s })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(keys: &[PrivateKey], m: Digest) -> Vec<MusigSession> {
+        let agg =
+            KeyAggregate::new(&keys.iter().map(|k| k.public_key()).collect::<Vec<_>>()).unwrap();
+        let mut sessions = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| MusigSession::new(k, agg.clone(), m, &[i as u8; 32]).unwrap())
+            .collect::<Vec<_>>();
+
+        let commitments = sessions.iter().map(|s| s.commitment()).collect::<Vec<_>>();
+        for s in &mut sessions {
+            assert!(s.reveal().is_err());
+            for c in &commitments {
+                s.add_commitment(c).unwrap();
+            }
+        }
+        let reveals = sessions
+            .iter()
+            .map(|s| s.reveal().unwrap())
+            .collect::<Vec<_>>();
+        for s in &mut sessions {
+            for r in &reveals {
+                s.add_reveal(r).unwrap();
+            }
+        }
+        sessions
+    }
+
+    #[test]
+    fn test_musig() {
+        let keys = [123u64, 456, 789].map(|k| PrivateKey(U256::from_u64(k)));
+        let m = Digest([Belt(1), Belt(2), Belt(3), Belt(4), Belt(5)]);
+        let mut sessions = run(&keys, m);
+
+        let partials = sessions
+            .iter_mut()
+            .map(|s| s.partial_sign().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sessions[0].partial_sign().unwrap_err(),
+            MusigError::WrongRound
+        );
+        for s in &mut sessions {
+            for p in &partials {
+                s.add_partial(p).unwrap();
+            }
+        }
+
+        let sig = sessions[0].finalize().unwrap();
+        assert_eq!(sig, sessions[2].finalize().unwrap());
+        let agg = sessions[0].key_aggregate().public_key();
+        assert!(agg.verify(&m, &sig));
+
+        // Coefficients make the aggregate differ from the plain key sum
+        assert_ne!(agg, keys.iter().map(|k| k.public_key()).sum());
+    }
+
+    #[test]
+    fn test_musig_rejects_cheating() {
+        let keys = [123u64, 456].map(|k| PrivateKey(U256::from_u64(k)));
+        let m = Digest([Belt(1), Belt(2), Belt(3), Belt(4), Belt(5)]);
+
+        assert_eq!(
+            KeyAggregate::new(&[keys[0].public_key(), keys[0].public_key()]),
+            Err(MusigError::InvalidKeySet)
+        );
+
+        // Nonce that does not match the commitment
+        let agg =
+            KeyAggregate::new(&keys.iter().map(|k| k.public_key()).collect::<Vec<_>>()).unwrap();
+        let mut a = MusigSession::new(&keys[0], agg.clone(), m, &[0; 32]).unwrap();
+        let b = MusigSession::new(&keys[1], agg, m, &[1; 32]).unwrap();
+        a.add_commitment(&a.commitment()).unwrap();
+        a.add_commitment(&b.commitment()).unwrap();
+        let mut bad = a.reveal().unwrap();
+        bad.signer = keys[1].public_key();
+        assert_eq!(
+            a.add_reveal(&bad),
+            Err(MusigError::CommitmentMismatch(keys[1].public_key()))
+        );
+
+        // Tampered partial signature
+        let mut sessions = run(&keys, m);
+        let mut partial = sessions[1].partial_sign().unwrap();
+        partial.s = partial.s.add_mod(&U256::ONE, &G_ORDER);
+        assert_eq!(
+            sessions[0].add_partial(&partial),
+            Err(MusigError::InvalidPartialSignature(keys[1].public_key()))
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! M-of-N Shamir secret sharing of BIP39 entropy, for cold-storage backups.
+//!
+//! This is not SLIP-39: shares are not mnemonics and there are no groups or passphrase
+//! encryption. The scheme is plain Shamir sharing over GF(2⁸) (AES polynomial `x⁸+x⁴+x³+x+1`),
+//! applied byte-wise to `secret || sha256(secret)[..4]`, so a wrong combination of shares is
+//! detected on recovery. Share `i` is the polynomials evaluated at `x = i` (`1..=n`).
+//!
+//! Each share is a base58 string of
+//!
+//! ```text
+//! version (1) | id (2, BE) | threshold (1) | index (1) | value | checksum (4)
+//! ```
+//!
+//! where `id` is random per split, so shares of different secrets are not mixed, and the
+//! checksum is the first 4 bytes of `sha256(sha256(…))` over the preceding bytes.
+//!
+//! Splitting a mnemonic shares its entropy; recovering gives back the same mnemonic, and so the
+//! same [`ExtendedKey`](crate::ExtendedKey) and PKH.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use bip39::Mnemonic;
+use crypto_bigint::subtle::ConstantTimeEq;
+use rand_core::CryptoRngCore;
+use sha2::{Digest as _, Sha256};
+use zeroize::Zeroizing;
+
+/// Current share encoding version.
+pub const SHARE_VERSION: u8 = 1;
+
+const SHARE_HEADER_LEN: usize = 1 + 2 + 1 + 1;
+const SHARE_CHECKSUM_LEN: usize = 4;
+const SECRET_DIGEST_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShamirError {
+    /// Threshold is zero or larger than the number of shares.
+    InvalidThreshold,
+    /// The share string is malformed or fails its checksum.
+    InvalidShare,
+    UnsupportedVersion(u8),
+    NotEnoughShares {
+        threshold: u8,
+        got: usize,
+    },
+    /// The shares come from different splits.
+    Mismatch,
+    DuplicateIndex(u8),
+    /// The recovered secret does not match its digest.
+    Checksum,
+    Mnemonic(bip39::Error),
+}
+
+impl core::fmt::Display for ShamirError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ShamirError::InvalidThreshold => write!(f, "Invalid share threshold"),
+            ShamirError::InvalidShare => write!(f, "Invalid share"),
+            ShamirError::UnsupportedVersion(v) => write!(f, "Unsupported share version {v}"),
+            ShamirError::NotEnoughShares { threshold, got } => {
+                write!(f, "Need {threshold} shares, got {got}")
+            }
+            ShamirError::Mismatch => write!(f, "Shares belong to different secrets"),
+            ShamirError::DuplicateIndex(i) => write!(f, "Share {i} given more than once"),
+            ShamirError::Checksum => write!(f, "Recovered secret fails its checksum"),
+            ShamirError::Mnemonic(e) => write!(f, "Invalid mnemonic: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for ShamirError {}
+
+#[derive(Clone)]
+pub struct Share {
+    /// Identifies the split this share belongs to
+    pub id: u16,
+    pub threshold: u8,
+    /// Evaluation point, `1..=n`
+    pub index: u8,
+    pub value: Zeroizing<Vec<u8>>,
+}
+
+impl core::fmt::Debug for Share {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Share")
+            .field("id", &self.id)
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+impl PartialEq for Share {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.threshold == other.threshold
+            && self.index == other.index
+            && bool::from(self.value.ct_eq(&other.value))
+    }
+}
+
+impl Eq for Share {}
+
+impl Share {
+    fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(
+            SHARE_HEADER_LEN + self.value.len() + SHARE_CHECKSUM_LEN,
+        ));
+        bytes.push(SHARE_VERSION);
+        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend_from_slice(&self.value);
+        let checksum = share_checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+}
+
+fn share_checksum(bytes: &[u8]) -> [u8; SHARE_CHECKSUM_LEN] {
+    let hash = Sha256::digest(Sha256::digest(bytes));
+    let mut checksum = [0u8; SHARE_CHECKSUM_LEN];
+    checksum.copy_from_slice(&hash[..SHARE_CHECKSUM_LEN]);
+    checksum
+}
+
+impl core::fmt::Display for Share {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&bs58::encode(&self.to_bytes()[..]).into_string())
+    }
+}
+
+impl core::str::FromStr for Share {
+    type Err = ShamirError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = Zeroizing::new(
+            bs58::decode(s.trim())
+                .into_vec()
+                .map_err(|_| ShamirError::InvalidShare)?,
+        );
+        if bytes.len() <= SHARE_HEADER_LEN + SHARE_CHECKSUM_LEN {
+            return Err(ShamirError::InvalidShare);
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - SHARE_CHECKSUM_LEN);
+        if share_checksum(body) != checksum {
+            return Err(ShamirError::InvalidShare);
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(ShamirError::UnsupportedVersion(body[0]));
+        }
+        if body[3] == 0 {
+            return Err(ShamirError::InvalidShare);
+        }
+        Ok(Share {
+            id: u16::from_be_bytes([body[1], body[2]]),
+            threshold: body[3],
+            index: body[4],
+            value: Zeroizing::new(body[SHARE_HEADER_LEN..].to_vec()),
+        })
+    }
+}
+
+/// Multiplication in GF(2⁸), without secret-dependent branches.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut r = 0;
+    for _ in 0..8 {
+        r ^= a & 0u8.wrapping_sub(b & 1);
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(a >> 7));
+        b >>= 1;
+    }
+    r
+}
+
+/// Inverse in GF(2⁸) as `a²⁵⁴`.
+fn gf_inv(a: u8) -> u8 {
+    let mut acc = 1;
+    for bit in (0..8).rev() {
+        acc = gf_mul(acc, acc);
+        if (254 >> bit) & 1 == 1 {
+            acc = gf_mul(acc, a);
+        }
+    }
+    acc
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which recover it.
+pub fn split_secret(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    rng: &mut impl CryptoRngCore,
+) -> Result<Vec<Share>, ShamirError> {
+    if threshold == 0 || threshold > count {
+        return Err(ShamirError::InvalidThreshold);
+    }
+
+    let mut payload = Zeroizing::new(secret.to_vec());
+    payload.extend_from_slice(&Sha256::digest(secret)[..SECRET_DIGEST_LEN]);
+
+    let mut id = [0u8; 2];
+    rng.fill_bytes(&mut id);
+    let id = u16::from_be_bytes(id);
+
+    // Coefficients of degree 1..threshold for every byte of the payload, lowest degree first
+    let degree = threshold as usize - 1;
+    let mut coefficients = Zeroizing::new(vec![0u8; payload.len() * degree]);
+    rng.fill_bytes(&mut coefficients);
+
+    Ok((1..=count)
+        .map(|x| {
+            let value = payload
+                .iter()
+                .enumerate()
+                .map(|(i, &secret_byte)| {
+                    // Horner evaluation, highest degree first
+                    let mut y = 0;
+                    for d in (0..degree).rev() {
+                        y = gf_mul(y, x) ^ coefficients[d * payload.len() + i];
+                    }
+                    gf_mul(y, x) ^ secret_byte
+                })
+                .collect();
+            Share {
+                id,
+                threshold,
+                index: x,
+                value: Zeroizing::new(value),
+            }
+        })
+        .collect())
+}
+
+/// Recover a secret from at least `threshold` shares of the same split.
+pub fn combine_shares(shares: &[Share]) -> Result<Zeroizing<Vec<u8>>, ShamirError> {
+    let first = shares.first().ok_or(ShamirError::NotEnoughShares {
+        threshold: 1,
+        got: 0,
+    })?;
+    if first.threshold == 0 {
+        return Err(ShamirError::InvalidThreshold);
+    }
+    if first.value.len() <= SECRET_DIGEST_LEN {
+        return Err(ShamirError::InvalidShare);
+    }
+    if shares.len() < first.threshold as usize {
+        return Err(ShamirError::NotEnoughShares {
+            threshold: first.threshold,
+            got: shares.len(),
+        });
+    }
+    let shares = &shares[..first.threshold as usize];
+    for (i, share) in shares.iter().enumerate() {
+        if share.id != first.id
+            || share.threshold != first.threshold
+            || share.value.len() != first.value.len()
+        {
+            return Err(ShamirError::Mismatch);
+        }
+        if share.index == 0 {
+            return Err(ShamirError::InvalidShare);
+        }
+        if shares[..i].iter().any(|s| s.index == share.index) {
+            return Err(ShamirError::DuplicateIndex(share.index));
+        }
+    }
+
+    // Lagrange interpolation at x = 0
+    let mut payload = Zeroizing::new(vec![0u8; first.value.len()]);
+    for share in shares {
+        let mut basis = 1;
+        for other in shares.iter().filter(|s| s.index != share.index) {
+            basis = gf_mul(
+                basis,
+                gf_mul(other.index, gf_inv(other.index ^ share.index)),
+            );
+        }
+        for (p, &y) in payload.iter_mut().zip(share.value.iter()) {
+            *p ^= gf_mul(basis, y);
+        }
+    }
+
+    let (secret, digest) = payload.split_at(payload.len() - SECRET_DIGEST_LEN);
+    if !bool::from(Sha256::digest(secret)[..SECRET_DIGEST_LEN].ct_eq(digest)) {
+        return Err(ShamirError::Checksum);
+    }
+    Ok(Zeroizing::new(secret.to_vec()))
+}
+
+/// Split the entropy of `mnemonic` into shares.
+pub fn split_mnemonic(
+    mnemonic: &Mnemonic,
+    threshold: u8,
+    count: u8,
+    rng: &mut impl CryptoRngCore,
+) -> Result<Vec<String>, ShamirError> {
+    let entropy = Zeroizing::new(mnemonic.to_entropy());
+    Ok(split_secret(&entropy, threshold, count, rng)?
+        .iter()
+        .map(|share| share.to_string())
+        .collect())
+}
+
+/// Recover a mnemonic from share strings produced by [`split_mnemonic`].
+pub fn recover_mnemonic<S: AsRef<str>>(shares: &[S]) -> Result<Mnemonic, ShamirError> {
+    let shares = shares
+        .iter()
+        .map(|s| s.as_ref().parse())
+        .collect::<Result<Vec<Share>, _>>()?;
+    let entropy = combine_shares(&shares)?;
+    Mnemonic::from_entropy(&entropy).map_err(ShamirError::Mnemonic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derive_master_key;
+    use iris_ztd::Hashable;
+    use rand_chacha::rand_core::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const LOG_MNEMONIC: &str = "pass destroy hub reject cricket flight camp garden scale liquid increase pool miracle fly tower file door cage vault tone night zero push crime";
+
+    #[test]
+    fn test_gf256() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+        assert_eq!(gf_mul(0x53, 0xca), 0x01);
+    }
+
+    #[test]
+    fn test_split_keygen_mnemonic() {
+        let mnemonic = Mnemonic::parse(LOG_MNEMONIC).unwrap();
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let shares = split_mnemonic(&mnemonic, 2, 3, &mut rng).unwrap();
+        assert_eq!(
+            shares,
+            [
+                "36qSCtAdxAbzk5a6goLY1nZJ921vCrqzHymDGNmmQ751NPjzQU53YruqJXmbv",
+                "36qSCtbq9xYbrrQykEnEioo51mnYtMicNR4HycL4LQuG4EY99izCJaLHVFkq1",
+                "36qSCtgcixp6KbcAV7GeQvU1BQmm6xMoaCzDFnP9hk14yhs25yu1W6B7tFm5W",
+            ]
+        );
+
+        let share: Share = shares[0].parse().unwrap();
+        let debug = alloc::format!("{share:?}");
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains(&alloc::format!("{:?}", &share.value[..])));
+
+        // Any two shares recover the test_keygen key
+        for pair in [[0, 1], [1, 2], [2, 0]] {
+            let recovered = recover_mnemonic(&[&shares[pair[0]], &shares[pair[1]]]).unwrap();
+            assert_eq!(recovered.to_string(), LOG_MNEMONIC);
+            let key = derive_master_key(&recovered.to_seed(""));
+            assert_eq!(
+                hex::encode(key.private_key.unwrap().to_be_bytes()),
+                "362b4073814e43f427983a83f11efcceb6741082c18f0d64b7e47340ba4485ba"
+            );
+            assert_eq!(
+                key.public_key.hash().to_string(),
+                "AyzPiJoqcqmdZdjxZ9aGLnVsbYcCphidHERKBWVXyKhNqTirshTmicG"
+            );
+        }
+
+        assert_eq!(
+            recover_mnemonic(&shares[..1]).unwrap_err(),
+            ShamirError::NotEnoughShares {
+                threshold: 2,
+                got: 1
+            }
+        );
+        assert_eq!(
+            recover_mnemonic(&[&shares[0], &shares[0]]).unwrap_err(),
+            ShamirError::DuplicateIndex(1)
+        );
+
+        // Shares of a different split
+        let other = split_mnemonic(&mnemonic, 2, 3, &mut rng).unwrap();
+        assert_eq!(
+            recover_mnemonic(&[&shares[0], &other[1]]).unwrap_err(),
+            ShamirError::Mismatch
+        );
+
+        // Typo in a share
+        let mut typo = shares[0].clone().into_bytes();
+        typo[10] = if typo[10] == b'2' { b'3' } else { b'2' };
+        assert_eq!(
+            recover_mnemonic(&[String::from_utf8(typo).unwrap(), shares[1].clone()]).unwrap_err(),
+            ShamirError::InvalidShare
+        );
+    }
+
+    #[test]
+    fn test_threshold_edges() {
+        let mut rng = ChaCha20Rng::from_seed([1u8; 32]);
+        let secret = [7u8; 16];
+        assert_eq!(
+            split_secret(&secret, 0, 3, &mut rng).unwrap_err(),
+            ShamirError::InvalidThreshold
+        );
+        assert_eq!(
+            split_secret(&secret, 4, 3, &mut rng).unwrap_err(),
+            ShamirError::InvalidThreshold
+        );
+
+        // 1-of-n shares are the secret itself; n-of-n needs all of them
+        let shares = split_secret(&secret, 1, 2, &mut rng).unwrap();
+        assert_eq!(&combine_shares(&shares[1..]).unwrap()[..], &secret);
+        let shares = split_secret(&secret, 5, 5, &mut rng).unwrap();
+        assert_eq!(&combine_shares(&shares).unwrap()[..], &secret);
+        assert!(combine_shares(&shares[1..]).is_err());
+
+        // Forged shares with a zero threshold or no room for the digest are rejected
+        let forged = Share {
+            id: 1,
+            threshold: 0,
+            index: 1,
+            value: Zeroizing::new(vec![7u8]),
+        };
+        assert_eq!(
+            forged.to_string().parse::<Share>().unwrap_err(),
+            ShamirError::InvalidShare
+        );
+        assert_eq!(
+            combine_shares(core::slice::from_ref(&forged)).unwrap_err(),
+            ShamirError::InvalidThreshold
+        );
+        let short = Share {
+            threshold: 1,
+            ..forged
+        };
+        assert_eq!(
+            combine_shares(&[short.to_string().parse().unwrap()]).unwrap_err(),
+            ShamirError::InvalidShare
+        );
+    }
+}
@@ -1,38 +1,411 @@
 use arrayvec::ArrayVec;
 use hmac::{Hmac, Mac};
-use iris_ztd::crypto::cheetah::{ch_add, ch_scal_big, A_GEN, G_ORDER};
-use iris_ztd::U256;
-use sha2::Sha512;
+use iris_ztd::crypto::cheetah::{ch_add, ch_scal_base, CheetahPoint, G_ORDER};
+use iris_ztd::{Hashable, U256};
+use sha2::{Digest as _, Sha256, Sha512};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 use crate::cheetah::{PrivateKey, PublicKey};
 
-fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Zeroizing<[u8; 64]> {
     let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
     mac.update(data);
-    mac.finalize().into_bytes().into()
+    Zeroizing::new(mac.finalize().into_bytes().into())
 }
 
+/// Version bytes of a serialized extended private key.
+pub const XPRV_VERSION: [u8; 4] = *b"nprv";
+/// Version bytes of a serialized extended public key.
+pub const XPUB_VERSION: [u8; 4] = *b"npub";
+
+/// version, depth, parent fingerprint, child index and chain code
+const XKEY_HEADER_LEN: usize = 4 + 1 + 4 + 4 + 32;
+const XPRV_LEN: usize = XKEY_HEADER_LEN + 1 + 32;
+const XPUB_LEN: usize = XKEY_HEADER_LEN + 97;
+const XKEY_CHECKSUM_LEN: usize = 4;
+const XKEY_MAX_LEN: usize = XPUB_LEN + XKEY_CHECKSUM_LEN;
+const XKEY_BS58_BUF: usize = 210;
+
 /// SLIP-10 Extended Key (private or public key + chain code)
-#[derive(Debug, Clone)]
+///
+/// The private key and chain code are wiped on drop and redacted from `Debug` output.
+#[derive(Clone)]
 pub struct ExtendedKey {
     pub private_key: Option<PrivateKey>,
     pub public_key: PublicKey,
-    pub chain_code: [u8; 32],
+    pub chain_code: Zeroizing<[u8; 32]>,
+    /// Number of derivation steps from the master key
+    pub depth: u8,
+    /// [`ExtendedKey::fingerprint`] of the parent key, zero for the master key
+    pub parent_fingerprint: [u8; 4],
+    /// Index this key was derived at, zero for the master key
+    pub child_index: u32,
+}
+
+impl core::fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ExtendedKey")
+            .field("private_key", &self.private_key)
+            .field("public_key", &self.public_key)
+            .field("chain_code", &format_args!("<redacted>"))
+            .field("depth", &self.depth)
+            .field("parent_fingerprint", &self.parent_fingerprint)
+            .field("child_index", &self.child_index)
+            .finish()
+    }
+}
+
+impl Zeroize for ExtendedKey {
+    fn zeroize(&mut self) {
+        if let Some(private_key) = self.private_key.as_mut() {
+            private_key.zeroize();
+        }
+        self.chain_code.zeroize();
+    }
+}
+
+// Both secret fields wipe themselves on drop
+impl ZeroizeOnDrop for ExtendedKey {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtendedKeyError {
+    Base58Decode(bs58::decode::Error),
+    InvalidLength(usize),
+    InvalidChecksum,
+    UnknownVersion([u8; 4]),
+    InvalidPrivateKey,
+    InvalidPublicKey,
+}
+
+impl core::fmt::Display for ExtendedKeyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ExtendedKeyError::Base58Decode(e) => write!(f, "Invalid base58: {e}"),
+            ExtendedKeyError::InvalidLength(len) => {
+                write!(f, "Invalid extended key length: {len}")
+            }
+            ExtendedKeyError::InvalidChecksum => write!(f, "Invalid extended key checksum"),
+            ExtendedKeyError::UnknownVersion(v) => {
+                write!(f, "Unknown extended key version: {v:02x?}")
+            }
+            ExtendedKeyError::InvalidPrivateKey => write!(f, "Invalid private key"),
+            ExtendedKeyError::InvalidPublicKey => write!(f, "Invalid public key"),
+        }
+    }
+}
+
+impl core::error::Error for ExtendedKeyError {}
+
+/// Offset of hardened child indices.
+pub const HARDENED: u32 = 1 << 31;
+
+/// Longest path that can be parsed; extended keys store their depth in a byte.
+const MAX_PATH_DEPTH: usize = u8::MAX as usize;
+
+/// A SLIP-10 derivation path such as `m/44'/0/5`.
+///
+/// Hardened steps are marked with `'` (or `h`/`H` when parsing) and stored with [`HARDENED`]
+/// added to the index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DerivationPath(ArrayVec<u32, MAX_PATH_DEPTH>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationPathError {
+    /// The path does not start with `m`.
+    MissingMaster,
+    /// A step is empty, not a number, or at least [`HARDENED`].
+    InvalidIndex,
+    TooDeep,
+}
+
+impl core::fmt::Display for DerivationPathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DerivationPathError::MissingMaster => write!(f, "Derivation path must start with m"),
+            DerivationPathError::InvalidIndex => write!(f, "Invalid derivation path index"),
+            DerivationPathError::TooDeep => {
+                write!(f, "Derivation path is longer than {MAX_PATH_DEPTH} steps")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DerivationPathError {}
+
+impl DerivationPath {
+    /// The empty path, `m`.
+    pub fn master() -> Self {
+        Self::default()
+    }
+
+    /// Raw child indices, hardened ones offset by [`HARDENED`].
+    pub fn indices(&self) -> &[u32] {
+        &self.0
+    }
+
+    /// Append a normal step.
+    pub fn child(mut self, index: u32) -> Result<Self, DerivationPathError> {
+        if index >= HARDENED {
+            return Err(DerivationPathError::InvalidIndex);
+        }
+        self.0
+            .try_push(index)
+            .map_err(|_| DerivationPathError::TooDeep)?;
+        Ok(self)
+    }
+
+    /// Append a hardened step.
+    pub fn hardened(mut self, index: u32) -> Result<Self, DerivationPathError> {
+        if index >= HARDENED {
+            return Err(DerivationPathError::InvalidIndex);
+        }
+        self.0
+            .try_push(index + HARDENED)
+            .map_err(|_| DerivationPathError::TooDeep)?;
+        Ok(self)
+    }
+}
+
+impl core::str::FromStr for DerivationPath {
+    type Err = DerivationPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = s.split('/');
+        if steps.next() != Some("m") {
+            return Err(DerivationPathError::MissingMaster);
+        }
+        steps.try_fold(Self::master(), |path, step| {
+            match step.strip_suffix(['\'', 'h', 'H']) {
+                Some(index) => path.hardened(parse_path_index(index)?),
+                None => path.child(parse_path_index(step)?),
+            }
+        })
+    }
+}
+
+fn parse_path_index(s: &str) -> Result<u32, DerivationPathError> {
+    // u32::from_str also accepts a leading `+`
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(DerivationPathError::InvalidIndex);
+    }
+    s.parse().map_err(|_| DerivationPathError::InvalidIndex)
+}
+
+impl core::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("m")?;
+        for &index in self.indices() {
+            if index >= HARDENED {
+                write!(f, "/{}'", index - HARDENED)?;
+            } else {
+                write!(f, "/{index}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeriveError {
+    /// Hardened derivation at this index needs the private key.
+    HardenedFromPublic(u32),
+    /// The key is already at the deepest depth an extended key can record.
+    TooDeep,
+}
+
+impl core::fmt::Display for DeriveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DeriveError::HardenedFromPublic(index) => write!(
+                f,
+                "Cannot derive hardened child {}' without private key",
+                index - HARDENED
+            ),
+            DeriveError::TooDeep => {
+                write!(f, "Cannot derive below depth {}", u8::MAX)
+            }
+        }
+    }
+}
+
+impl core::error::Error for DeriveError {}
+
+fn xkey_checksum(payload: &[u8]) -> [u8; XKEY_CHECKSUM_LEN] {
+    let hash = Sha256::digest(Sha256::digest(payload));
+    let mut checksum = [0u8; XKEY_CHECKSUM_LEN];
+    checksum.copy_from_slice(&hash[..XKEY_CHECKSUM_LEN]);
+    checksum
 }
 
 impl ExtendedKey {
+    /// Identifier of this key, used as `parent_fingerprint` of its children.
+    ///
+    /// The top 32 bits of the first belt of the public key hash.
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let hash = self.public_key.hash();
+        let mut fingerprint = [0u8; 4];
+        fingerprint.copy_from_slice(&hash.0[0].0.to_be_bytes()[..4]);
+        fingerprint
+    }
+
+    /// Serialize without the checksum.
+    ///
+    /// Layout: `version (4) | depth (1) | parent fingerprint (4) | child index (4, BE) |
+    /// chain code (32) | key`, where the key is `0x00 || private key (32, BE)` for
+    /// [`XPRV_VERSION`] and the 97-byte public key for [`XPUB_VERSION`].
+    fn to_payload(&self, public: bool) -> Zeroizing<ArrayVec<u8, XKEY_MAX_LEN>> {
+        let mut payload = Zeroizing::new(ArrayVec::new());
+        let private_key = self.private_key.as_ref().filter(|_| !public);
+        payload
+            .try_extend_from_slice(match private_key {
+                Some(_) => &XPRV_VERSION,
+                None => &XPUB_VERSION,
+            })
+            .unwrap();
+        payload.push(self.depth);
+        payload
+            .try_extend_from_slice(&self.parent_fingerprint)
+            .unwrap();
+        payload
+            .try_extend_from_slice(&self.child_index.to_be_bytes())
+            .unwrap();
+        payload.try_extend_from_slice(&self.chain_code[..]).unwrap();
+        match private_key {
+            Some(pk) => {
+                payload.push(0x00);
+                payload
+                    .try_extend_from_slice(&Zeroizing::new(pk.to_be_bytes())[..])
+                    .unwrap();
+            }
+            None => payload
+                .try_extend_from_slice(&self.public_key.to_be_bytes())
+                .unwrap(),
+        }
+        payload
+    }
+
+    fn fmt_base58(&self, f: &mut core::fmt::Formatter<'_>, public: bool) -> core::fmt::Result {
+        let mut payload = self.to_payload(public);
+        let checksum = xkey_checksum(&payload);
+        payload.try_extend_from_slice(&checksum).unwrap();
+        let mut buf = Zeroizing::new([0u8; XKEY_BS58_BUF]);
+        let len = bs58::encode(&payload[..])
+            .onto(&mut buf[..])
+            .map_err(|_| core::fmt::Error)?;
+        f.write_str(core::str::from_utf8(&buf[..len]).map_err(|_| core::fmt::Error)?)
+    }
+
+    /// The extended public key string of this key, even if it holds a private key.
+    pub fn xpub(&self) -> impl core::fmt::Display + '_ {
+        struct Xpub<'a>(&'a ExtendedKey);
+
+        impl core::fmt::Display for Xpub<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                self.0.fmt_base58(f, true)
+            }
+        }
+
+        Xpub(self)
+    }
+
+    /// Parse a base58check extended private or public key.
+    pub fn from_base58(s: &str) -> Result<Self, ExtendedKeyError> {
+        let mut buf = Zeroizing::new([0u8; XKEY_MAX_LEN]);
+        let len = bs58::decode(s)
+            .onto(&mut buf[..])
+            .map_err(ExtendedKeyError::Base58Decode)?;
+        if len < XKEY_HEADER_LEN + XKEY_CHECKSUM_LEN {
+            return Err(ExtendedKeyError::InvalidLength(len));
+        }
+        let (payload, checksum) = buf[..len].split_at(len - XKEY_CHECKSUM_LEN);
+        if xkey_checksum(payload) != checksum {
+            return Err(ExtendedKeyError::InvalidChecksum);
+        }
+
+        let version: [u8; 4] = payload[..4].try_into().unwrap();
+        let expected_len = match version {
+            XPRV_VERSION => XPRV_LEN,
+            XPUB_VERSION => XPUB_LEN,
+            _ => return Err(ExtendedKeyError::UnknownVersion(version)),
+        };
+        if payload.len() != expected_len {
+            return Err(ExtendedKeyError::InvalidLength(payload.len()));
+        }
+
+        let depth = payload[4];
+        let parent_fingerprint = payload[5..9].try_into().unwrap();
+        let child_index = u32::from_be_bytes(payload[9..13].try_into().unwrap());
+        let chain_code = Zeroizing::new(payload[13..XKEY_HEADER_LEN].try_into().unwrap());
+        let key = &payload[XKEY_HEADER_LEN..];
+
+        let (private_key, public_key) = if version == XPRV_VERSION {
+            let s = U256::from_be_slice(&key[1..]);
+            if key[0] != 0x00 || s == U256::ZERO || s >= G_ORDER {
+                return Err(ExtendedKeyError::InvalidPrivateKey);
+            }
+            let private_key = PrivateKey(s);
+            let public_key = private_key.public_key();
+            (Some(private_key), public_key)
+        } else {
+            let point =
+                CheetahPoint::from_bytes(key).map_err(|_| ExtendedKeyError::InvalidPublicKey)?;
+            (None, PublicKey(point))
+        };
+
+        Ok(ExtendedKey {
+            private_key,
+            public_key,
+            chain_code,
+            depth,
+            parent_fingerprint,
+            child_index,
+        })
+    }
+
+    /// Derive the descendant at `path`, relative to this key.
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<ExtendedKey, DeriveError> {
+        let mut key = self.clone();
+        for &index in path.indices() {
+            key = key.try_derive_child(index)?;
+        }
+        Ok(key)
+    }
+
+    /// Drop the private key, leaving a watch-only key that can only derive non-hardened children.
+    pub fn neuter(&self) -> ExtendedKey {
+        ExtendedKey {
+            private_key: None,
+            ..self.clone()
+        }
+    }
+
     /// Derive a child key at the given index using SLIP-10
+    ///
+    /// # Panics
+    ///
+    /// If `index` is hardened and this key has no private key, or this key is at depth 255; see
+    /// [`ExtendedKey::try_derive_child`].
     pub fn derive_child(&self, index: u32) -> ExtendedKey {
-        let hardened = index >= (1 << 31);
+        self.try_derive_child(index)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Derive a child key at the given index using SLIP-10
+    ///
+    /// Indices at or above [`HARDENED`] derive hardened children, which requires the private key.
+    /// Fails with [`DeriveError::TooDeep`] if this key is already at depth 255.
+    pub fn try_derive_child(&self, index: u32) -> Result<ExtendedKey, DeriveError> {
+        let hardened = index >= HARDENED;
+        let depth = self.depth.checked_add(1).ok_or(DeriveError::TooDeep)?;
 
-        let mut data = ArrayVec::<_, { 1 + 96 + 4 }>::new();
+        let mut data = Zeroizing::new(ArrayVec::<_, { 1 + 96 + 4 }>::new());
         if hardened {
             let private_key = self
                 .private_key
                 .as_ref()
-                .expect("Cannot derive hardened child without private key");
+                .ok_or(DeriveError::HardenedFromPublic(index))?;
             data.push(0x00);
-            data.try_extend_from_slice(&private_key.to_be_bytes())
+            data.try_extend_from_slice(&Zeroizing::new(private_key.to_be_bytes())[..])
                 .unwrap();
             data.try_extend_from_slice(&index.to_be_bytes()).unwrap();
         } else {
@@ -41,64 +414,89 @@ impl ExtendedKey {
                 .unwrap();
             data.try_extend_from_slice(&index.to_be_bytes()).unwrap();
         }
-        let mut result = hmac_sha512(&self.chain_code, &data);
+        let mut result = hmac_sha512(&self.chain_code[..], &data);
 
         loop {
-            let left = U256::from_be_slice(&result[..32]);
-            let mut chain_code = [0u8; 32];
+            let left = Zeroizing::new(U256::from_be_slice(&result[..32]));
+            let mut chain_code = Zeroizing::new([0u8; 32]);
             chain_code.copy_from_slice(&result[32..]);
 
-            if left < G_ORDER {
+            if *left < G_ORDER {
                 match self.private_key.as_ref() {
                     Some(pk) => {
                         let s = left.add_mod(&pk.0, &G_ORDER);
                         if s != U256::ZERO {
                             let private_key = PrivateKey(s);
                             let public_key = private_key.public_key();
-                            return ExtendedKey {
+                            return Ok(ExtendedKey {
                                 private_key: Some(private_key),
                                 public_key,
                                 chain_code,
-                            };
+                                depth,
+                                parent_fingerprint: self.fingerprint(),
+                                child_index: index,
+                            });
                         }
                     }
                     None => {
-                        let mut point = ch_scal_big(&left, &A_GEN).unwrap();
+                        let mut point = ch_scal_base(&left);
                         point = ch_add(&point, &self.public_key.0).unwrap();
                         if !point.inf {
-                            return ExtendedKey {
+                            return Ok(ExtendedKey {
                                 private_key: None,
                                 public_key: PublicKey(point),
                                 chain_code,
-                            };
+                                depth,
+                                parent_fingerprint: self.fingerprint(),
+                                child_index: index,
+                            });
                         }
                     }
                 }
             }
             // Invalid key: rehash 0x01 || right || index
-            let mut data = ArrayVec::<_, { 1 + 32 + 4 }>::new();
+            let mut data = Zeroizing::new(ArrayVec::<_, { 1 + 32 + 4 }>::new());
             data.push(0x01);
-            data.try_extend_from_slice(&chain_code).unwrap();
+            data.try_extend_from_slice(&chain_code[..]).unwrap();
             data.try_extend_from_slice(&index.to_be_bytes()).unwrap();
-            result = hmac_sha512(&self.chain_code, &data);
+            result = hmac_sha512(&self.chain_code[..], &data);
         }
     }
 }
 
+/// Serializes as a base58check extended private key if the private key is present, and as an
+/// extended public key otherwise. Use [`ExtendedKey::xpub`] to share a watch-only key.
+impl core::fmt::Display for ExtendedKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.fmt_base58(f, false)
+    }
+}
+
+impl core::str::FromStr for ExtendedKey {
+    type Err = ExtendedKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_base58(s)
+    }
+}
+
 pub fn derive_master_key(seed: &[u8]) -> ExtendedKey {
     const DOMAIN_SEPARATOR: &[u8] = b"Nockchain seed";
     let mut result = hmac_sha512(DOMAIN_SEPARATOR, seed);
     loop {
-        let s = U256::from_be_slice(&result[..32]);
-        let mut chain_code = [0u8; 32];
+        let s = Zeroizing::new(U256::from_be_slice(&result[..32]));
+        let mut chain_code = Zeroizing::new([0u8; 32]);
         chain_code.copy_from_slice(&result[32..]);
-        if s < G_ORDER && s != U256::ZERO {
-            let private_key = PrivateKey(s);
+        if *s < G_ORDER && *s != U256::ZERO {
+            let private_key = PrivateKey(*s);
             let public_key = private_key.public_key();
             return ExtendedKey {
                 private_key: Some(private_key),
                 public_key,
                 chain_code,
+                depth: 0,
+                parent_fingerprint: [0; 4],
+                child_index: 0,
             };
         }
         result = hmac_sha512(DOMAIN_SEPARATOR, &result[..]);
@@ -161,6 +559,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extended_key_serialization() {
+        use alloc::string::ToString;
+
+        let master = derive_master_key(&[7u8; 64]);
+        let account = master.derive_child(1 << 31).derive_child(3);
+        assert_eq!(account.depth, 2);
+        assert_eq!(account.child_index, 3);
+        assert_eq!(
+            account.parent_fingerprint,
+            master.derive_child(1 << 31).fingerprint()
+        );
+
+        let xprv = account.to_string();
+        let parsed: ExtendedKey = xprv.parse().unwrap();
+        assert_eq!(parsed.to_string(), xprv);
+        assert_eq!(
+            parsed.private_key.unwrap().0,
+            account.private_key.as_ref().unwrap().0
+        );
+        assert_eq!(parsed.public_key, account.public_key);
+
+        // Watch-only wallet derives the same receive addresses
+        let xpub = account.xpub().to_string();
+        let watch_only: ExtendedKey = xpub.parse().unwrap();
+        assert!(watch_only.private_key.is_none());
+        assert_eq!(watch_only.to_string(), xpub);
+        for i in 0..3 {
+            let child = watch_only.derive_child(i);
+            assert_eq!(child.public_key, account.derive_child(i).public_key);
+            assert_eq!(child.parent_fingerprint, account.fingerprint());
+        }
+
+        // Corrupted checksum
+        let mut bytes = bs58::decode(&xpub).into_vec().unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_eq!(
+            bs58::encode(bytes)
+                .into_string()
+                .parse::<ExtendedKey>()
+                .unwrap_err(),
+            ExtendedKeyError::InvalidChecksum
+        );
+    }
+
+    #[test]
+    fn test_derivation_path() {
+        use alloc::string::ToString;
+
+        let path: DerivationPath = "m/44'/0h/7/5".parse().unwrap();
+        assert_eq!(path.indices(), &[44 + HARDENED, HARDENED, 7, 5]);
+        assert_eq!(path.to_string(), "m/44'/0'/7/5");
+        assert_eq!(
+            "m".parse::<DerivationPath>().unwrap(),
+            DerivationPath::master()
+        );
+        for bad in [
+            "",
+            "44'/0",
+            "m/",
+            "m//1",
+            "m/x",
+            "m/+1",
+            "m/2147483648",
+            "m/1''",
+        ] {
+            assert!(bad.parse::<DerivationPath>().is_err(), "{bad}");
+        }
+
+        let master = derive_master_key(&[9u8; 64]);
+        let key = master.derive_path(&path).unwrap();
+        let expected = master
+            .derive_child(44 + HARDENED)
+            .derive_child(HARDENED)
+            .derive_child(7)
+            .derive_child(5);
+        assert_eq!(key.public_key, expected.public_key);
+        assert_eq!(key.chain_code, expected.chain_code);
+        assert_eq!(key.depth, 4);
+
+        // Neutered keys derive the public half of non-hardened paths only
+        let account = master.derive_path(&"m/44'/0'".parse().unwrap()).unwrap();
+        let watch_only = account.neuter();
+        assert!(watch_only.private_key.is_none());
+        let receive: DerivationPath = "m/7/5".parse().unwrap();
+        assert_eq!(
+            watch_only.derive_path(&receive).unwrap().public_key,
+            key.public_key
+        );
+        assert_eq!(
+            watch_only
+                .derive_path(&"m/1'".parse().unwrap())
+                .unwrap_err(),
+            DeriveError::HardenedFromPublic(1 + HARDENED)
+        );
+
+        // The depth byte cannot wrap around
+        let deepest = ExtendedKey {
+            depth: u8::MAX,
+            ..master.clone()
+        };
+        assert_eq!(
+            deepest.try_derive_child(0).unwrap_err(),
+            DeriveError::TooDeep
+        );
+        assert_eq!(
+            deepest.derive_path(&"m/0".parse().unwrap()).unwrap_err(),
+            DeriveError::TooDeep
+        );
+    }
+
     #[test]
     fn test_nockchain_message_vector() {
         // Test vector from: nockchain-wallet sign-message "hello"
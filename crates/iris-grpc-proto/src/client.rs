@@ -1,8 +1,24 @@
-// Client module is only available for native targets (not WASM)
-#![cfg(not(target_arch = "wasm32"))]
-
-use iris_nockchain_types::{BalanceUpdate, RawTx, TxId};
-use tonic::transport::Channel;
+//! Transport-agnostic gRPC clients.
+//!
+//! [`NockchainClient`] holds request building, pagination, conversion and error mapping for the
+//! public API over any tonic [`GrpcService`]: a [`Channel`] natively
+//! ([`PublicNockchainGrpcClient`]), or a gRPC-Web client in the browser.
+//!
+//! Balances are fetched page by page against one snapshot (block height and id). If a page
+//! comes back from a different snapshot, e.g. because a block arrived mid-listing, the pages
+//! collected so far are dropped and the listing restarts, up to a bounded number of retries.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+
+use iris_nockchain_types::{Balance, BalanceUpdate, RawTx, TxId};
+use iris_ztd::Digest;
+use tonic::client::GrpcService;
+use tonic::codegen::{Body, Bytes, StdError};
+#[cfg(not(target_arch = "wasm32"))]
+use tonic::transport::{Channel, Endpoint};
+
+use crate::discovery::BalanceSource;
 
 use crate::pb::common::v1::{Base58Hash, Base58Pubkey, PageRequest};
 use crate::pb::common::{v1 as pb_common_v1, v2 as pb_common_v2};
@@ -11,8 +27,15 @@ use crate::pb::public::v2::*;
 
 pub type Result<T> = std::result::Result<T, ClientError>;
 
+/// Number of times a balance listing is restarted when the snapshot changes under it.
+pub const DEFAULT_SNAPSHOT_RETRIES: u32 = 3;
+
+/// Number of pages a balance listing may take before it is abandoned.
+pub const DEFAULT_MAX_PAGES: u32 = 10_000;
+
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("Transport error: {0}")]
     Transport(#[from] tonic::transport::Error),
 
@@ -28,13 +51,17 @@ pub enum ClientError {
     #[error("Snapshot changed during pagination; retry")]
     SnapshotChanged,
 
+    #[error("Server repeated page token {0:?}")]
+    RepeatedPageToken(String),
+
+    #[error("Balance listing exceeded {0} pages")]
+    TooManyPages(u32),
+
     #[error("Conversion error: {0}")]
     Conversion(#[from] crate::common::ConversionError),
-}
 
-#[derive(Clone)]
-pub struct PublicNockchainGrpcClient {
-    client: NockchainServiceClient<Channel>,
+    #[error("Failed to cue noun: {0}")]
+    Cue(#[from] iris_ztd::CueError),
 }
 
 pub enum BalanceRequest {
@@ -42,37 +69,111 @@ pub enum BalanceRequest {
     FirstName(String),
 }
 
+impl BalanceRequest {
+    fn selector(&self) -> wallet_get_balance_request::Selector {
+        match self {
+            BalanceRequest::Address(addr) => {
+                wallet_get_balance_request::Selector::Address(Base58Pubkey { key: addr.clone() })
+            }
+            BalanceRequest::FirstName(fname) => {
+                wallet_get_balance_request::Selector::FirstName(Base58Hash {
+                    hash: fname.clone(),
+                })
+            }
+        }
+    }
+}
+
+/// Client for the public Nockchain API over the transport `T`.
+#[derive(Clone)]
+pub struct NockchainClient<T> {
+    client: NockchainServiceClient<T>,
+    snapshot_retries: u32,
+    max_pages: u32,
+}
+
+/// [`NockchainClient`] over a native tonic channel.
+#[cfg(not(target_arch = "wasm32"))]
+pub type PublicNockchainGrpcClient = NockchainClient<Channel>;
+
+#[cfg(not(target_arch = "wasm32"))]
 impl PublicNockchainGrpcClient {
-    pub async fn connect<T: AsRef<str>>(address: T) -> Result<Self> {
-        let client = NockchainServiceClient::connect(address.as_ref().to_string()).await?;
-        Ok(Self { client })
+    pub async fn connect<A: AsRef<str>>(address: A) -> Result<Self> {
+        let channel = Endpoint::new(address.as_ref().to_string())?
+            .connect()
+            .await?;
+        Ok(Self::new(channel))
+    }
+
+    /// Use an already established channel, e.g. one with a custom connector.
+    pub fn from_channel(channel: Channel) -> Self {
+        Self::new(channel)
+    }
+}
+
+impl<T> NockchainClient<T>
+where
+    T: GrpcService<tonic::body::BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    pub fn new(transport: T) -> Self {
+        Self {
+            client: NockchainServiceClient::new(transport),
+            snapshot_retries: DEFAULT_SNAPSHOT_RETRIES,
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+
+    /// Restart a balance listing up to `retries` times if the snapshot changes mid-listing.
+    pub fn with_snapshot_retries(mut self, retries: u32) -> Self {
+        self.snapshot_retries = retries;
+        self
     }
 
+    /// Give up on a balance listing after `max_pages` pages.
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Fetch every page of a balance, retrying if a new block arrives while paging.
     pub async fn wallet_get_balance(&mut self, request: &BalanceRequest) -> Result<BalanceUpdate> {
+        let mut attempt = 0;
+        loop {
+            match self.wallet_get_balance_snapshot(request).await {
+                Err(ClientError::SnapshotChanged) if attempt < self.snapshot_retries => {
+                    attempt += 1
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fetch every page of a balance from a single snapshot.
+    ///
+    /// Fails with [`ClientError::SnapshotChanged`] if the pages do not all come from the same
+    /// snapshot, and stops with [`ClientError::RepeatedPageToken`] or
+    /// [`ClientError::TooManyPages`] if the server keeps paging.
+    pub async fn wallet_get_balance_snapshot(
+        &mut self,
+        request: &BalanceRequest,
+    ) -> Result<BalanceUpdate> {
         let mut page_token = String::new();
         let mut all_notes: Vec<pb_common_v2::BalanceEntry> = Vec::new();
-        let mut height: Option<pb_common_v1::BlockHeight> = None;
-        let mut block_id: Option<pb_common_v1::Hash> = None;
+        let mut snapshot = None;
+        let mut seen_tokens = BTreeSet::new();
 
         loop {
-            let sel = match request {
-                BalanceRequest::Address(addr) => {
-                    wallet_get_balance_request::Selector::Address(Base58Pubkey {
-                        key: addr.clone(),
-                    })
-                }
-                BalanceRequest::FirstName(fname) => {
-                    wallet_get_balance_request::Selector::FirstName(Base58Hash {
-                        hash: fname.clone(),
-                    })
-                }
-            };
-
+            if seen_tokens.len() as u32 >= self.max_pages {
+                return Err(ClientError::TooManyPages(self.max_pages));
+            }
             let req = WalletGetBalanceRequest {
-                selector: Some(sel),
+                selector: Some(request.selector()),
                 page: Some(PageRequest {
                     client_page_items_limit: 0, // let server choose default/cap
-                    page_token: page_token.clone(),
+                    page_token,
                     max_bytes: 0,
                 }),
             };
@@ -86,32 +187,24 @@ impl PublicNockchainGrpcClient {
                 None => return Err(ClientError::EmptyResponse),
             };
 
-            if height.is_none() {
-                height = balance.height;
-                block_id = balance.block_id;
-            }
-
-            if balance.height != height || balance.block_id != block_id {
-                return Err(ClientError::SnapshotChanged);
+            let page_snapshot = (balance.height, balance.block_id);
+            match &snapshot {
+                None => snapshot = Some(page_snapshot),
+                Some(first) if *first != page_snapshot => return Err(ClientError::SnapshotChanged),
+                Some(_) => {}
             }
 
-            all_notes.extend(balance.notes.into_iter());
-            page_token = balance
-                .page
-                .and_then(|p| {
-                    if p.next_page_token.is_empty() {
-                        None
-                    } else {
-                        Some(p.next_page_token)
-                    }
-                })
-                .unwrap_or_default();
-
+            all_notes.extend(balance.notes);
+            page_token = balance.page.map(|p| p.next_page_token).unwrap_or_default();
             if page_token.is_empty() {
                 break;
             }
+            if !seen_tokens.insert(page_token.clone()) {
+                return Err(ClientError::RepeatedPageToken(page_token));
+            }
         }
 
+        let (height, block_id) = snapshot.expect("at least one page was fetched");
         let pb_balance = pb_common_v2::Balance {
             notes: all_notes,
             height,
@@ -158,7 +251,7 @@ impl PublicNockchainGrpcClient {
             .into_inner();
 
         match response.result {
-            Some(transaction_accepted_response::Result::Accepted(_)) => Ok(true),
+            Some(transaction_accepted_response::Result::Accepted(accepted)) => Ok(accepted),
             Some(transaction_accepted_response::Result::Error(err)) => {
                 Err(ClientError::ServerError(err.message))
             }
@@ -167,6 +260,22 @@ impl PublicNockchainGrpcClient {
     }
 }
 
+/// Queries balances by PKH address.
+impl<T> BalanceSource for NockchainClient<T>
+where
+    T: GrpcService<tonic::body::BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    type Error = ClientError;
+
+    fn balance(&mut self, pkh: &Digest) -> impl Future<Output = Result<Balance>> {
+        let request = BalanceRequest::Address(pkh.to_string());
+        async move { Ok(self.wallet_get_balance(&request).await?.notes) }
+    }
+}
+
 #[cfg(feature = "private-api")]
 use crate::pb::common::v1::Wire;
 #[cfg(feature = "private-api")]
@@ -174,19 +283,49 @@ use crate::pb::private::v1::nock_app_service_client::NockAppServiceClient;
 #[cfg(feature = "private-api")]
 use crate::pb::private::v1::{peek_response, poke_response, PeekRequest, PokeRequest};
 #[cfg(feature = "private-api")]
-use iris_ztd::{cue, jam, Noun};
+use iris_ztd::{cue_with_limits, jam, CueLimits, Noun};
 
+/// Client for the private NockApp API over the transport `T`.
 #[cfg(feature = "private-api")]
 #[derive(Clone)]
-pub struct PrivateNockchainGrpcClient {
-    client: NockAppServiceClient<Channel>,
+pub struct PrivateNockchainClient<T> {
+    client: NockAppServiceClient<T>,
+    cue_limits: CueLimits,
 }
 
-#[cfg(feature = "private-api")]
+/// [`PrivateNockchainClient`] over a native tonic channel.
+#[cfg(all(feature = "private-api", not(target_arch = "wasm32")))]
+pub type PrivateNockchainGrpcClient = PrivateNockchainClient<Channel>;
+
+#[cfg(all(feature = "private-api", not(target_arch = "wasm32")))]
 impl PrivateNockchainGrpcClient {
-    pub async fn connect<T: AsRef<str>>(address: T) -> Result<Self> {
-        let client = NockAppServiceClient::connect(address.as_ref().to_string()).await?;
-        Ok(Self { client })
+    pub async fn connect<A: AsRef<str>>(address: A) -> Result<Self> {
+        let channel = Endpoint::new(address.as_ref().to_string())?
+            .connect()
+            .await?;
+        Ok(Self::new(channel))
+    }
+}
+
+#[cfg(feature = "private-api")]
+impl<T> PrivateNockchainClient<T>
+where
+    T: GrpcService<tonic::body::BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    pub fn new(transport: T) -> Self {
+        Self {
+            client: NockAppServiceClient::new(transport),
+            cue_limits: CueLimits::default(),
+        }
+    }
+
+    /// Set the limits applied when decoding peek responses.
+    pub fn with_cue_limits(mut self, cue_limits: CueLimits) -> Self {
+        self.cue_limits = cue_limits;
+        self
     }
 
     pub async fn peek(&mut self, pid: i32, path: &Noun) -> Result<Noun> {
@@ -199,9 +338,9 @@ impl PrivateNockchainGrpcClient {
         let response = self.client.peek(request).await?.into_inner();
 
         match response.result {
-            Some(peek_response::Result::Data(data)) => cue(&data).ok_or_else(|| {
-                ClientError::ServerError("Failed to cue noun from peek response".into())
-            }),
+            Some(peek_response::Result::Data(data)) => {
+                Ok(cue_with_limits(&data, &self.cue_limits)?)
+            }
             Some(peek_response::Result::Error(err)) => Err(ClientError::ServerError(err.message)),
             None => Err(ClientError::EmptyResponse),
         }
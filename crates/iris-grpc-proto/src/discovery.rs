@@ -0,0 +1,175 @@
+//! Gap-limit discovery of funded addresses under an extended key.
+//!
+//! Walks the non-hardened children `0, 1, 2, …` of an account key, queries the balance of each
+//! child's PKH through a [`BalanceSource`], and stops once `gap_limit` consecutive children hold
+//! no notes. Works with watch-only keys (see [`ExtendedKey::neuter`]).
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::future::Future;
+
+use iris_crypto::slip10::HARDENED;
+use iris_crypto::{DeriveError, ExtendedKey};
+use iris_nockchain_types::{Balance, Name, Note};
+use iris_ztd::{Digest, Hashable, ZMap};
+
+/// Anything that can report the notes currently held by a PKH.
+pub trait BalanceSource {
+    type Error;
+
+    fn balance(&mut self, pkh: &Digest) -> impl Future<Output = Result<Balance, Self::Error>>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DiscoveryError<E> {
+    #[error("Derivation error: {0}")]
+    Derive(#[from] DeriveError),
+
+    #[error("Balance source error: {0}")]
+    Source(E),
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredBalance {
+    /// All notes found, across every derived address.
+    pub balance: Balance,
+    /// Derivation index of the address holding each note.
+    pub indices: BTreeMap<Name, u32>,
+    /// First index after the last funded address, i.e. the next fresh receive address.
+    pub next_index: u32,
+}
+
+impl DiscoveredBalance {
+    pub fn index_of(&self, name: &Name) -> Option<u32> {
+        self.indices.get(name).copied()
+    }
+}
+
+/// Discover the funded children of `account`, stopping after `gap_limit` empty ones in a row.
+///
+/// An address counts as unused if it currently holds no notes, so addresses that were funded
+/// and then fully spent also count towards the gap.
+pub async fn discover_balance<S: BalanceSource>(
+    source: &mut S,
+    account: &ExtendedKey,
+    gap_limit: u32,
+) -> Result<DiscoveredBalance, DiscoveryError<S::Error>> {
+    let mut notes = ZMap::new();
+    let mut indices = BTreeMap::new();
+    let mut next_index = 0;
+
+    let mut index = 0;
+    while index < HARDENED && index - next_index < gap_limit {
+        let child = account.try_derive_child(index)?;
+        let balance = source
+            .balance(&child.public_key.hash())
+            .await
+            .map_err(DiscoveryError::Source)?;
+        for (name, note) in balance.0 {
+            indices.insert(name, index);
+            notes.insert(name, note);
+            next_index = index + 1;
+        }
+        index += 1;
+    }
+
+    Ok(DiscoveredBalance {
+        balance: Balance(notes),
+        indices,
+        next_index,
+    })
+}
+
+/// In-memory [`BalanceSource`] for tests.
+#[derive(Debug, Clone, Default)]
+pub struct MockBalanceSource {
+    notes: BTreeMap<Digest, Vec<Note>>,
+    /// PKHs queried so far, in order.
+    pub queries: Vec<Digest>,
+}
+
+impl MockBalanceSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Credit `note` to `pkh`.
+    pub fn insert(&mut self, pkh: Digest, note: Note) -> &mut Self {
+        self.notes.entry(pkh).or_default().push(note);
+        self
+    }
+}
+
+impl BalanceSource for MockBalanceSource {
+    type Error = Infallible;
+
+    fn balance(&mut self, pkh: &Digest) -> impl Future<Output = Result<Balance, Self::Error>> {
+        self.queries.push(*pkh);
+        let mut balance = ZMap::new();
+        for note in self.notes.get(pkh).into_iter().flatten() {
+            balance.insert(note.name(), note.clone());
+        }
+        std::future::ready(Ok(Balance(balance)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iris_crypto::derive_master_key;
+    use iris_nockchain_types::v1::{NoteData, NoteV1};
+    use iris_nockchain_types::{Nicks, Version};
+
+    fn note(seed: u64, assets: u64) -> Note {
+        Note::V1(NoteV1 {
+            version: Version::V1,
+            origin_page: 1,
+            name: Name::new(seed.hash(), (seed, assets).hash()),
+            note_data: NoteData::empty(),
+            assets: Nicks(assets),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_gap_limit_discovery() {
+        let account = derive_master_key(&[3u8; 64])
+            .derive_path(&"m/44'/0'".parse().unwrap())
+            .unwrap()
+            .neuter();
+        let pkh = |i| account.derive_child(i).public_key.hash();
+
+        let mut source = MockBalanceSource::new();
+        source
+            .insert(pkh(0), note(1, 10))
+            .insert(pkh(0), note(2, 20))
+            .insert(pkh(3), note(3, 30))
+            // Beyond the gap after index 3
+            .insert(pkh(9), note(4, 40));
+
+        let found = discover_balance(&mut source, &account, 5).await.unwrap();
+        assert_eq!(found.balance.0.iter().count(), 3);
+        assert_eq!(found.next_index, 4);
+        assert_eq!(found.index_of(&note(2, 20).name()), Some(0));
+        assert_eq!(found.index_of(&note(3, 30).name()), Some(3));
+        assert_eq!(found.index_of(&note(4, 40).name()), None);
+        assert_eq!(source.queries, (0..9).map(pkh).collect::<Vec<_>>());
+
+        // A wider gap reaches the last address
+        let found = discover_balance(&mut source, &account, 6).await.unwrap();
+        assert_eq!(found.index_of(&note(4, 40).name()), Some(9));
+        assert_eq!(found.next_index, 10);
+
+        let found = discover_balance(&mut MockBalanceSource::new(), &account, 0)
+            .await
+            .unwrap();
+        assert_eq!(found.next_index, 0);
+
+        // No children below the deepest depth a key can record
+        let mut deepest = account.clone();
+        deepest.depth = u8::MAX;
+        assert!(matches!(
+            discover_balance(&mut MockBalanceSource::new(), &deepest, 5).await,
+            Err(DiscoveryError::Derive(DeriveError::TooDeep))
+        ));
+    }
+}
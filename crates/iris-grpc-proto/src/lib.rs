@@ -306,7 +306,9 @@ pub mod pb {
         include_bytes!(concat!(env!("OUT_DIR"), "/nockchain_descriptor.bin"));
 }
 
-#[cfg(not(target_arch = "wasm32"))]
 pub mod client;
 pub mod common;
 pub mod convert;
+pub mod discovery;
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
@@ -0,0 +1,638 @@
+//! In-memory [`NockchainService`] for integration tests.
+//!
+//! [`MockNockchainServer`] answers `WalletGetBalance`, `WalletSendTransaction` and
+//! `TransactionAccepted` from a [`MockLedger`] instead of a node, so clients and wallet flows can
+//! be tested end-to-end. Serve it on a local port with [`MockNockchainServer::serve_local`], or
+//! connect a client over an in-memory duplex with [`MockNockchainServer::connect_duplex`].
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use hyper_util::rt::TokioIo;
+use iris_nockchain_types::v1::{Pkh, SpendCondition, SpendV1 as Spend};
+use iris_nockchain_types::{Balance, BlockHeight, Name, Note, RawTx, TxEngineSettings, TxId};
+use iris_ztd::{Belt, Digest, ZMap};
+use tokio::task::JoinHandle;
+use tonic::transport::{Channel, Endpoint, Server};
+use tonic::{Request, Response, Status};
+
+use crate::client::PublicNockchainGrpcClient;
+use crate::pb::common::v1::{Acknowledged, ErrorCode, ErrorStatus, PageResponse};
+use crate::pb::common::v2::{Balance as PbBalance, BalanceEntry as PbBalanceEntry};
+use crate::pb::public::v2::nockchain_service_server::{NockchainService, NockchainServiceServer};
+use crate::pb::public::v2::*;
+
+/// Page size used when the client does not ask for one.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MockRpc {
+    WalletGetBalance,
+    WalletSendTransaction,
+    TransactionAccepted,
+}
+
+/// An injected failure for the next call of an RPC.
+#[derive(Debug, Clone)]
+pub enum MockFailure {
+    /// Fail the call with a gRPC status.
+    Status(Status),
+    /// Answer with an in-band `ErrorStatus`.
+    Error(ErrorCode, String),
+    /// Answer normally, then move to a new block, as if one arrived mid-pagination.
+    AdvanceBlock(Digest),
+}
+
+/// State behind a [`MockNockchainServer`].
+#[derive(Debug, Clone)]
+pub struct MockLedger {
+    pub height: BlockHeight,
+    pub block_id: Digest,
+    /// Notes held by each lock, keyed by the PKH queried as `address` or by a note first name.
+    pub balances: BTreeMap<Digest, Balance>,
+    /// Largest page the server returns, whatever the client asks for.
+    pub page_size: usize,
+    /// Hand back the requested page as the next one, like a server stuck on a page.
+    pub repeat_page_token: bool,
+    /// Settings used to compute the outputs of accepted transactions.
+    pub settings: TxEngineSettings,
+    /// Accept transactions as soon as they are submitted.
+    pub auto_accept: bool,
+    /// Every transaction submitted, accepted or not.
+    pub submitted: BTreeMap<TxId, RawTx>,
+    pub accepted: BTreeSet<TxId>,
+    failures: BTreeMap<MockRpc, VecDeque<MockFailure>>,
+}
+
+impl Default for MockLedger {
+    fn default() -> Self {
+        Self {
+            height: 1,
+            block_id: Digest([Belt(0); 5]),
+            balances: BTreeMap::new(),
+            page_size: DEFAULT_PAGE_SIZE,
+            repeat_page_token: false,
+            settings: TxEngineSettings::v1_default(),
+            auto_accept: true,
+            submitted: BTreeMap::new(),
+            accepted: BTreeSet::new(),
+            failures: BTreeMap::new(),
+        }
+    }
+}
+
+impl MockLedger {
+    /// Credit `note` to `lock`.
+    pub fn insert(&mut self, lock: Digest, note: Note) -> &mut Self {
+        self.balances
+            .entry(lock)
+            .or_insert_with(|| Balance(ZMap::new()))
+            .0
+            .insert(note.name(), note);
+        self
+    }
+
+    /// Fail the next call of `rpc`. Failures queue up and are consumed in order.
+    pub fn fail_next(&mut self, rpc: MockRpc, failure: MockFailure) -> &mut Self {
+        self.failures.entry(rpc).or_default().push_back(failure);
+        self
+    }
+
+    /// Move to a new block, invalidating outstanding page tokens.
+    pub fn advance_block(&mut self, block_id: Digest) -> &mut Self {
+        self.height += 1;
+        self.block_id = block_id;
+        self
+    }
+
+    /// Accept a submitted transaction: its inputs are removed from every balance and its
+    /// outputs are credited to their first names, and to their PKH for single-PKH locks.
+    pub fn accept(&mut self, tx_id: &TxId) -> bool {
+        let Some(tx) = self.submitted.get(tx_id).cloned() else {
+            return false;
+        };
+        if !self.accepted.insert(*tx_id) {
+            return true;
+        }
+
+        let spent: Vec<Name> = match &tx {
+            RawTx::V0(tx) => tx.inputs.0.iter().map(|(name, _)| *name).collect(),
+            RawTx::V1(tx) => tx.spends.0.iter().map(|(name, _)| *name).collect(),
+        };
+        for balance in self.balances.values_mut() {
+            for name in &spent {
+                balance.0.remove(name);
+            }
+        }
+        // Single-PKH outputs are also credited to the PKH, as the address index does. The PKHs
+        // considered are those the ledger already tracks and those unlocking this transaction.
+        let mut pkhs: BTreeSet<Digest> = self.balances.keys().copied().collect();
+        if let RawTx::V1(tx) = &tx {
+            for (_, spend) in tx.spends.0.iter() {
+                if let Spend::S1(spend) = spend {
+                    let sc = spend.witness.lock_merkle_proof.spend_condition();
+                    pkhs.extend(sc.pkh().flat_map(|p| p.hashes.iter().copied()));
+                }
+            }
+        }
+        let owners: BTreeMap<Digest, Digest> = pkhs
+            .into_iter()
+            .map(|pkh| (SpendCondition::new_pkh(Pkh::single(pkh)).first_name(), pkh))
+            .collect();
+        for note in tx.outputs(self.height, self.settings) {
+            if let Some(pkh) = owners.get(&note.name().first) {
+                self.insert(*pkh, note.clone());
+            }
+            self.insert(note.name().first, note);
+        }
+        true
+    }
+
+    /// Answer a call of `rpc` with `handler`, unless a failure is queued for it.
+    #[allow(clippy::result_large_err)]
+    fn respond<T>(
+        &mut self,
+        rpc: MockRpc,
+        handler: impl FnOnce(&mut Self) -> Result<T, ErrorStatus>,
+    ) -> Result<Result<T, ErrorStatus>, Status> {
+        match self.failures.get_mut(&rpc).and_then(VecDeque::pop_front) {
+            None => Ok(handler(self)),
+            Some(MockFailure::Status(status)) => Err(status),
+            Some(MockFailure::Error(code, message)) => Ok(Err(error_status(code, &message))),
+            Some(MockFailure::AdvanceBlock(block_id)) => {
+                let result = handler(self);
+                self.advance_block(block_id);
+                Ok(result)
+            }
+        }
+    }
+
+    fn notes(
+        &self,
+        selector: &wallet_get_balance_request::Selector,
+    ) -> Result<Vec<(Name, Note)>, ErrorStatus> {
+        match selector {
+            wallet_get_balance_request::Selector::Address(address) => {
+                let lock = Digest::try_from(address.key.as_str())
+                    .map_err(|_| error_status(ErrorCode::InvalidRequest, "Invalid address"))?;
+                Ok(self
+                    .balances
+                    .get(&lock)
+                    .map(|b| b.0.iter().map(|(n, note)| (*n, note.clone())).collect())
+                    .unwrap_or_default())
+            }
+            wallet_get_balance_request::Selector::FirstName(first_name) => {
+                let first = Digest::try_from(first_name.hash.as_str())
+                    .map_err(|_| error_status(ErrorCode::InvalidRequest, "Invalid first name"))?;
+                let mut notes = BTreeMap::new();
+                for (name, note) in self.balances.values().flat_map(|b| b.0.iter()) {
+                    if name.first == first {
+                        notes.insert(*name, note.clone());
+                    }
+                }
+                Ok(notes.into_iter().collect())
+            }
+        }
+    }
+
+    /// Page tokens are plain offsets. A token issued before a new block pages the new
+    /// snapshot, and the changed height and block id in the response tell the client.
+    fn parse_page_token(token: &str) -> Result<usize, ErrorStatus> {
+        if token.is_empty() {
+            return Ok(0);
+        }
+        token
+            .parse()
+            .map_err(|_| error_status(ErrorCode::InvalidRequest, "Invalid page token"))
+    }
+
+    fn get_balance(&self, request: WalletGetBalanceRequest) -> Result<PbBalance, ErrorStatus> {
+        let selector = request
+            .selector
+            .ok_or_else(|| error_status(ErrorCode::InvalidRequest, "Missing selector"))?;
+        let page = request.page.unwrap_or_default();
+        let offset = Self::parse_page_token(&page.page_token)?;
+        let limit = match page.client_page_items_limit as usize {
+            0 => self.page_size,
+            n => n.min(self.page_size),
+        }
+        .max(1);
+
+        let notes = self.notes(&selector)?;
+        let end = notes.len().min(offset.saturating_add(limit));
+        let next_page_token = if end >= notes.len() {
+            String::new()
+        } else if self.repeat_page_token {
+            offset.to_string()
+        } else {
+            end.to_string()
+        };
+
+        Ok(PbBalance {
+            notes: notes
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .map(|(name, note)| PbBalanceEntry {
+                    name: Some(name.into()),
+                    note: Some(note.into()),
+                })
+                .collect(),
+            height: Some(self.height.into()),
+            block_id: Some(self.block_id.into()),
+            page: Some(PageResponse { next_page_token }),
+        })
+    }
+
+    fn send_transaction(
+        &mut self,
+        request: WalletSendTransactionRequest,
+    ) -> Result<(), ErrorStatus> {
+        let invalid = |msg: &str| error_status(ErrorCode::InvalidRequest, msg);
+        let tx_id: Digest = request
+            .tx_id
+            .ok_or_else(|| invalid("Missing tx_id"))?
+            .try_into()
+            .map_err(|_| invalid("Invalid tx_id"))?;
+        let raw_tx: RawTx = request
+            .raw_tx
+            .ok_or_else(|| invalid("Missing raw_tx"))?
+            .try_into()
+            .map_err(|_| invalid("Invalid raw_tx"))?;
+        if raw_tx.id() != tx_id {
+            return Err(invalid("tx_id does not match raw_tx"));
+        }
+
+        self.submitted.insert(tx_id, raw_tx);
+        if self.auto_accept {
+            self.accept(&tx_id);
+        }
+        Ok(())
+    }
+
+    fn transaction_accepted(
+        &self,
+        request: TransactionAcceptedRequest,
+    ) -> Result<bool, ErrorStatus> {
+        let tx_id = request
+            .tx_id
+            .and_then(|id| Digest::try_from(id.hash.as_str()).ok())
+            .ok_or_else(|| error_status(ErrorCode::InvalidRequest, "Invalid tx_id"))?;
+        Ok(self.accepted.contains(&tx_id))
+    }
+}
+
+fn error_status(code: ErrorCode, message: &str) -> ErrorStatus {
+    ErrorStatus {
+        code: code.into(),
+        message: message.to_string(),
+        details: None,
+    }
+}
+
+/// A `NockchainService` backed by a [`MockLedger`].
+///
+/// Clones share the same ledger, so a test can keep one to inspect and mutate state while
+/// another is serving.
+#[derive(Debug, Clone, Default)]
+pub struct MockNockchainServer {
+    ledger: Arc<Mutex<MockLedger>>,
+}
+
+impl MockNockchainServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ledger(ledger: MockLedger) -> Self {
+        Self {
+            ledger: Arc::new(Mutex::new(ledger)),
+        }
+    }
+
+    pub fn ledger(&self) -> MutexGuard<'_, MockLedger> {
+        self.ledger.lock().expect("mock ledger poisoned")
+    }
+
+    pub fn into_service(self) -> NockchainServiceServer<Self> {
+        NockchainServiceServer::new(self)
+    }
+
+    /// Serve on `127.0.0.1` at an ephemeral port, returning the address to connect to.
+    pub async fn serve_local(
+        self,
+    ) -> std::io::Result<(SocketAddr, JoinHandle<Result<(), tonic::transport::Error>>)> {
+        let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let handle = tokio::spawn(
+            Server::builder()
+                .add_service(self.into_service())
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+        Ok((addr, handle))
+    }
+
+    /// Serve over an in-memory duplex and return a channel connected to it.
+    pub async fn duplex_channel(self) -> Result<Channel, tonic::transport::Error> {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(
+            Server::builder()
+                .add_service(self.into_service())
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server))),
+        );
+
+        let mut client = Some(client);
+        Endpoint::from_static("http://mock.nockchain")
+            .connect_with_connector(tower::service_fn(move |_| {
+                let client = client.take();
+                async move {
+                    client.map(TokioIo::new).ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotConnected,
+                            "duplex already connected",
+                        )
+                    })
+                }
+            }))
+            .await
+    }
+
+    /// Connect a [`PublicNockchainGrpcClient`] over an in-memory duplex.
+    pub async fn connect_duplex(
+        self,
+    ) -> Result<PublicNockchainGrpcClient, tonic::transport::Error> {
+        Ok(PublicNockchainGrpcClient::from_channel(
+            self.duplex_channel().await?,
+        ))
+    }
+}
+
+#[tonic::async_trait]
+impl NockchainService for MockNockchainServer {
+    async fn wallet_get_balance(
+        &self,
+        request: Request<WalletGetBalanceRequest>,
+    ) -> Result<Response<WalletGetBalanceResponse>, Status> {
+        let result = self.ledger().respond(MockRpc::WalletGetBalance, |ledger| {
+            ledger.get_balance(request.into_inner())
+        })?;
+        Ok(Response::new(WalletGetBalanceResponse {
+            result: Some(match result {
+                Ok(balance) => wallet_get_balance_response::Result::Balance(balance),
+                Err(e) => wallet_get_balance_response::Result::Error(e),
+            }),
+        }))
+    }
+
+    async fn wallet_send_transaction(
+        &self,
+        request: Request<WalletSendTransactionRequest>,
+    ) -> Result<Response<WalletSendTransactionResponse>, Status> {
+        let result = self
+            .ledger()
+            .respond(MockRpc::WalletSendTransaction, |ledger| {
+                ledger.send_transaction(request.into_inner())
+            })?;
+        Ok(Response::new(WalletSendTransactionResponse {
+            result: Some(match result {
+                Ok(()) => wallet_send_transaction_response::Result::Ack(Acknowledged {}),
+                Err(e) => wallet_send_transaction_response::Result::Error(e),
+            }),
+        }))
+    }
+
+    async fn transaction_accepted(
+        &self,
+        request: Request<TransactionAcceptedRequest>,
+    ) -> Result<Response<TransactionAcceptedResponse>, Status> {
+        let result = self
+            .ledger()
+            .respond(MockRpc::TransactionAccepted, |ledger| {
+                ledger.transaction_accepted(request.into_inner())
+            })?;
+        Ok(Response::new(TransactionAcceptedResponse {
+            result: Some(match result {
+                Ok(accepted) => transaction_accepted_response::Result::Accepted(accepted),
+                Err(e) => transaction_accepted_response::Result::Error(e),
+            }),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{BalanceRequest, ClientError, NockchainClient};
+    use crate::discovery::discover_balance;
+    use crate::pb::common::v1::Base58Hash;
+    use iris_crypto::derive_master_key;
+    use iris_nockchain_types::v1::{Lock, NoteData, NoteV1, SpendCondition};
+    use iris_nockchain_types::{Nicks, TxBuilder, Version};
+    use iris_ztd::Hashable;
+
+    fn note(seed: u64, assets: u64) -> Note {
+        Note::V1(NoteV1 {
+            version: Version::V1,
+            origin_page: 1,
+            name: Name::new(seed.hash(), (seed, assets).hash()),
+            note_data: NoteData::empty(),
+            assets: Nicks(assets),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_paginated_balance() {
+        let server = MockNockchainServer::new();
+        let pkh = derive_master_key(&[1u8; 64]).public_key.hash();
+        {
+            let mut ledger = server.ledger();
+            ledger.page_size = 2;
+            for i in 0..5 {
+                ledger.insert(pkh, note(i, 10 + i));
+            }
+        }
+
+        let mut client = server.clone().connect_duplex().await.unwrap();
+        let update = client
+            .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(update.notes.0.iter().count(), 5);
+        assert_eq!(update.height, 1);
+
+        let first = note(3, 13).name().first;
+        let update = client
+            .wallet_get_balance(&BalanceRequest::FirstName(first.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(update.notes.0.iter().count(), 1);
+
+        // A block arriving mid-listing restarts it
+        server.ledger().fail_next(
+            MockRpc::WalletGetBalance,
+            MockFailure::AdvanceBlock(7u64.hash()),
+        );
+        let update = client
+            .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(update.notes.0.iter().count(), 5);
+        assert_eq!((update.height, update.block_id), (2, 7u64.hash()));
+
+        let mut client = client.with_snapshot_retries(0);
+        server.ledger().fail_next(
+            MockRpc::WalletGetBalance,
+            MockFailure::AdvanceBlock(8u64.hash()),
+        );
+        assert!(matches!(
+            client
+                .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+                .await,
+            Err(ClientError::SnapshotChanged)
+        ));
+
+        // A server that keeps paging is cut off
+        server.ledger().repeat_page_token = true;
+        assert!(matches!(
+            client
+                .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+                .await,
+            Err(ClientError::RepeatedPageToken(token)) if token == "0"
+        ));
+        server.ledger().repeat_page_token = false;
+        let mut client = client.with_max_pages(2);
+        assert!(matches!(
+            client
+                .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+                .await,
+            Err(ClientError::TooManyPages(2))
+        ));
+        let mut client = client.with_max_pages(3);
+        assert!(client
+            .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_in_process_transport() {
+        // The service itself is a transport, with no channel in between
+        let server = MockNockchainServer::new();
+        let pkh = derive_master_key(&[4u8; 64]).public_key.hash();
+        server.ledger().insert(pkh, note(1, 10));
+        let mut client = NockchainClient::new(server.into_service());
+        let update = client
+            .wallet_get_balance(&BalanceRequest::Address(pkh.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(update.notes.0.iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_and_tcp() {
+        let server = MockNockchainServer::new();
+        server
+            .ledger()
+            .fail_next(
+                MockRpc::WalletGetBalance,
+                MockFailure::Status(Status::unavailable("node down")),
+            )
+            .fail_next(
+                MockRpc::WalletGetBalance,
+                MockFailure::Error(ErrorCode::Timeout, "peek timed out".into()),
+            );
+
+        let (addr, _handle) = server.clone().serve_local().await.unwrap();
+        let mut client = PublicNockchainGrpcClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap();
+        let request = BalanceRequest::Address(Digest([Belt(0); 5]).to_string());
+        assert!(matches!(
+            client.wallet_get_balance(&request).await,
+            Err(ClientError::Status(s)) if s.code() == tonic::Code::Unavailable
+        ));
+        assert!(matches!(
+            client.wallet_get_balance(&request).await,
+            Err(ClientError::ServerError(m)) if m == "peek timed out"
+        ));
+        assert!(client.wallet_get_balance(&request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_and_discover() {
+        let account = derive_master_key(&[2u8; 64]);
+        let key = account.private_key.clone().unwrap();
+        let pkh = account.public_key.hash();
+        let lock: Lock = SpendCondition::new_pkh(iris_nockchain_types::v1::Pkh::single(pkh)).into();
+        let funded = Note::V1(NoteV1 {
+            version: Version::V1,
+            origin_page: 1,
+            name: Name::new(lock.hash(), 1u64.hash()),
+            note_data: NoteData::empty(),
+            assets: Nicks(100_000_000),
+        });
+
+        let server = MockNockchainServer::new();
+        server.ledger().auto_accept = false;
+        server.ledger().insert(pkh, funded.clone());
+        let mut client = server.clone().connect_duplex().await.unwrap();
+
+        let mut builder = TxBuilder::new(server.ledger().settings);
+        builder
+            .simple_spend(
+                vec![(funded.clone(), Some((lock, 0)))],
+                derive_master_key(&[3u8; 64]).public_key.hash(),
+                Nicks(50_000),
+                pkh,
+                false,
+            )
+            .unwrap();
+        builder.sign(&key);
+        let raw_tx = RawTx::V1(builder.build().to_raw_tx());
+
+        let tx_id = client.wallet_send_transaction(&raw_tx).await.unwrap();
+        let base58_id = Base58Hash {
+            hash: tx_id.to_string(),
+        };
+        assert!(!client
+            .transaction_accepted(base58_id.clone())
+            .await
+            .unwrap());
+
+        assert!(server.ledger().accept(&tx_id));
+        assert!(client.transaction_accepted(base58_id).await.unwrap());
+        let outputs = {
+            let ledger = server.ledger();
+            raw_tx.outputs(ledger.height, ledger.settings)
+        };
+        assert_eq!(outputs.len(), 2);
+        for note in &outputs {
+            assert!(server.ledger().balances[&note.name().first]
+                .0
+                .get_key_value(&note.name())
+                .is_some());
+        }
+
+        // The refund is visible by address, and the spent note is gone
+        let by_address: Vec<Name> = server.ledger().balances[&pkh]
+            .0
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        let refund = outputs
+            .iter()
+            .find(|note| note.assets() > Nicks(50_000))
+            .unwrap();
+        assert_eq!(by_address, [refund.name()]);
+
+        // Gap-limit discovery through the gRPC client
+        let account = account.derive_path(&"m/44'/0'".parse().unwrap()).unwrap();
+        server
+            .ledger()
+            .insert(account.derive_child(2).public_key.hash(), note(9, 90));
+        let found = discover_balance(&mut client, &account, 3).await.unwrap();
+        assert_eq!(found.next_index, 3);
+        assert_eq!(found.index_of(&note(9, 90).name()), Some(2));
+    }
+}
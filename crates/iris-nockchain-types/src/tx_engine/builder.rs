@@ -99,17 +99,22 @@ impl NoteInfo {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SpendBuilder {
-    note_info: NoteInfo,
+    pub(crate) note_info: NoteInfo,
     #[serde(
         serialize_with = "noun_serialize",
         deserialize_with = "noun_deserialize"
     )]
-    spend: Spend,
+    pub(crate) spend: Spend,
     #[serde(
         serialize_with = "noun_serialize",
         deserialize_with = "noun_deserialize"
     )]
-    refund_lock: Option<LockRoot>,
+    pub(crate) refund_lock: Option<LockRoot>,
+    /// Input note and spend condition, if known. Needed to export a PSTX.
+    #[serde(default)]
+    pub(crate) note: Option<Note>,
+    #[serde(default)]
+    pub(crate) spend_condition: Option<(Lock, usize)>,
 }
 
 impl SpendBuilder {
@@ -118,6 +123,8 @@ impl SpendBuilder {
         spend_condition: Option<(Lock, usize)>,
         refund_lock: Option<LockRoot>,
     ) -> Result<Self, BuildError> {
+        let input_note = note.clone();
+        let input_spend_condition = spend_condition.clone();
         let (note_info, spend) = match note {
             Note::V0(note) => {
                 let note_info = NoteInfo {
@@ -151,6 +158,8 @@ impl SpendBuilder {
             note_info,
             spend,
             refund_lock,
+            note: Some(input_note),
+            spend_condition: input_spend_condition,
         })
     }
 
@@ -160,6 +169,8 @@ impl SpendBuilder {
             note_info,
             spend,
             refund_lock,
+            note: None,
+            spend_condition: None,
         })
     }
 
@@ -174,6 +185,8 @@ impl SpendBuilder {
             note_info,
             spend,
             refund_lock,
+            note: None,
+            spend_condition: None,
         })
     }
 
@@ -378,7 +391,7 @@ impl SpendBuilder {
         false
     }
 
-    fn missing_unlocks_fee(&self, settings: &TxEngineSettings) -> Nicks {
+    pub(crate) fn missing_unlocks_fee(&self, settings: &TxEngineSettings) -> Nicks {
         let mut fee = Nicks(0);
 
         for mu in self.missing_unlocks() {
@@ -399,9 +412,9 @@ impl SpendBuilder {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TxBuilder {
-    spends: BTreeMap<Name, SpendBuilder>,
-    fee_pool: Vec<SpendBuilder>,
-    settings: TxEngineSettings,
+    pub(crate) spends: BTreeMap<Name, SpendBuilder>,
+    pub(crate) fee_pool: Vec<SpendBuilder>,
+    pub(crate) settings: TxEngineSettings,
 }
 
 impl TxBuilder {
@@ -479,40 +492,87 @@ impl TxBuilder {
         refund_pkh: Digest,
         include_lock_data: bool,
     ) -> Result<&mut Self, BuildError> {
-        if gift == 0 {
+        let recipient = LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(recipient)).into());
+        self.multi_spend_base(
+            notes,
+            vec![(recipient, gift)],
+            refund_pkh,
+            include_lock_data,
+        )
+    }
+
+    /// Pay each of `recipients` from `notes`, without setting the fee.
+    ///
+    /// Notes are drained in order, and a single note may pay several recipients. Recipients
+    /// sharing a lock root are merged into a single seed. Notes that end up paying nobody are kept
+    /// in the fee pool. Lock data is only included for recipients given as a full `Lock`.
+    pub fn multi_spend_base(
+        &mut self,
+        notes: Vec<(Note, Option<(Lock, usize)>)>,
+        recipients: Vec<(LockRoot, Nicks)>,
+        refund_pkh: Digest,
+        include_lock_data: bool,
+    ) -> Result<&mut Self, BuildError> {
+        let mut outputs: Vec<(LockRoot, Nicks)> = vec![];
+        for (lock_root, gift) in recipients {
+            let lrh = lock_root.hash();
+            match outputs.iter_mut().find(|(lr, _)| lr.hash() == lrh) {
+                Some((lr, total)) => {
+                    // Keep the full lock around, if any of the entries has it
+                    if let LockRoot::Lock(_) = lock_root {
+                        *lr = lock_root;
+                    }
+                    *total = total.checked_add(gift).ok_or(BuildError::AmountOverflow)?;
+                }
+                None => outputs.push((lock_root, gift)),
+            }
+        }
+
+        if outputs.is_empty() || outputs.iter().any(|(_, gift)| *gift == 0) {
             return Err(BuildError::ZeroGift);
         }
 
         let refund_lock = Lock::from(SpendCondition::new_pkh(Pkh::single(refund_pkh)));
         let refund_lock_root = LockRoot::Lock(refund_lock);
-
-        let mut remaining_gift = gift;
+        let mut outputs = outputs.into_iter();
+        let mut current = outputs.next();
 
         for (note, spend_condition) in notes {
-            let gift_portion = remaining_gift.min(note.assets());
-
-            remaining_gift -= gift_portion;
+            let mut assets_left = note.assets();
 
             let mut spend =
                 SpendBuilder::new(note, spend_condition, Some(refund_lock_root.clone()))?;
-            if gift_portion > 0 {
-                let seed = spend.build_seed(
-                    LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(recipient)).into()),
-                    gift_portion,
-                    include_lock_data,
-                );
+            let mut used = false;
+
+            while assets_left > 0 {
+                let Some((lock_root, remaining_gift)) = &mut current else {
+                    break;
+                };
+
+                let gift_portion = (*remaining_gift).min(assets_left);
+                *remaining_gift -= gift_portion;
+                assets_left -= gift_portion;
+
+                let include_lock_data = include_lock_data && matches!(lock_root, LockRoot::Lock(_));
+                let seed = spend.build_seed(lock_root.clone(), gift_portion, include_lock_data);
                 spend.seed(seed);
-                spend.compute_refund(include_lock_data);
-                assert!(spend.is_balanced());
+                used = true;
+
+                if *remaining_gift == 0 {
+                    current = outputs.next();
+                }
+            }
+
+            spend.compute_refund(include_lock_data);
+            assert!(spend.is_balanced());
+            if used {
                 self.spend(spend);
             } else {
-                spend.compute_refund(include_lock_data);
-                assert!(spend.is_balanced());
                 self.fee_pool.push(spend);
             }
         }
 
-        if remaining_gift > 0 {
+        if current.is_some() {
             return Err(BuildError::InsufficientFunds);
         }
 
@@ -533,6 +593,33 @@ impl TxBuilder {
         Ok(self)
     }
 
+    /// Pay several recipients in a single transaction, and set the fee across all spends.
+    ///
+    /// See [`TxBuilder::multi_spend_base`] for how notes are assigned to recipients. Paying to
+    /// the refund lock itself is rejected, as the gift would merge into the refund.
+    pub fn multi_spend(
+        &mut self,
+        notes: Vec<(Note, Option<(Lock, usize)>)>,
+        recipients: Vec<(LockRoot, Nicks)>,
+        refund_pkh: Digest,
+        include_lock_data: bool,
+    ) -> Result<&mut Self, BuildError> {
+        // A gift seed under the refund lock would be folded into the refund and eaten by the fee
+        let refund_lock_root =
+            LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(refund_pkh)).into());
+        if recipients
+            .iter()
+            .any(|(lr, _)| lr.hash() == refund_lock_root.hash())
+        {
+            return Err(BuildError::RecipientIsRefund);
+        }
+
+        self.multi_spend_base(notes, recipients, refund_pkh, include_lock_data)?
+            .recalc_and_set_fee(include_lock_data)?;
+
+        Ok(self)
+    }
+
     pub fn add_preimage(&mut self, preimage: Noun) -> Option<Digest> {
         let mut ret = None;
         for (_, s) in self.spends.iter_mut() {
@@ -631,20 +718,7 @@ impl TxBuilder {
     }
 
     pub fn calc_fee(&self) -> Nicks {
-        let mut fee = Nicks(0);
-
-        let (sw, ww) = words_for_unordered_spends(
-            self.spends.values().map(|v| (v.note_info.name, &v.spend)),
-            &self.settings,
-        );
-        fee += self.settings.cost_per_word * sw
-            + self.settings.cost_per_word * ww / self.settings.witness_word_div;
-
-        for s in self.spends.values() {
-            fee += s.missing_unlocks_fee(&self.settings);
-        }
-
-        fee.max(self.settings.min_fee)
+        fee_for_spends(self.spends.values(), &self.settings)
     }
 
     pub fn recalc_and_set_fee(&mut self, include_lock_data: bool) -> Result<&mut Self, BuildError> {
@@ -839,6 +913,26 @@ impl TxBuilder {
     }
 }
 
+/// Fee of a transaction made of `spends`, counting unlocks that are still missing from them.
+pub(crate) fn fee_for_spends<'a>(
+    spends: impl Iterator<Item = &'a SpendBuilder> + Clone,
+    settings: &TxEngineSettings,
+) -> Nicks {
+    let mut fee = Nicks(0);
+
+    let (sw, ww) = words_for_unordered_spends(
+        spends.clone().map(|v| (v.note_info.name, &v.spend)),
+        settings,
+    );
+    fee += settings.cost_per_word * sw + settings.cost_per_word * ww / settings.witness_word_div;
+
+    for s in spends {
+        fee += s.missing_unlocks_fee(settings);
+    }
+
+    fee.max(settings.min_fee)
+}
+
 #[derive(Debug)]
 pub enum BuildError {
     ZeroGift,
@@ -851,6 +945,8 @@ pub enum BuildError {
     UnbalancedSpends,
     MissingSpendCondition,
     MissingUnlocks(Vec<MissingUnlocks>),
+    AmountOverflow,
+    RecipientIsRefund,
 }
 
 impl core::fmt::Display for BuildError {
@@ -891,6 +987,10 @@ impl core::fmt::Display for BuildError {
                 }
                 Ok(())
             }
+            BuildError::AmountOverflow => write!(f, "Amounts overflow the range of nicks"),
+            BuildError::RecipientIsRefund => {
+                write!(f, "Recipient lock is the same as the refund lock")
+            }
         }
     }
 }
@@ -1135,6 +1235,192 @@ mod tests {
         builder.validate().unwrap();
     }
 
+    #[test]
+    fn test_multi_spend() {
+        let (private_key, public_key) = keys();
+
+        let notes = [
+            "7yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+            "6yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+            "5yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, last)| {
+            Note::V1(v1::NoteV1 {
+                version: Version::V1,
+                origin_page: 13 + i as u32,
+                name: Name::new(
+                    "2H7WHTE9dFXiGgx4J432DsCLuMovNkokfcnCGRg7utWGM9h13PgQvsH"
+                        .try_into()
+                        .unwrap(),
+                    last.try_into().unwrap(),
+                ),
+                note_data: NoteData::empty(),
+                assets: Nicks(3000),
+            })
+        })
+        .collect::<Vec<_>>();
+        let spend_condition: (Lock, usize) = (
+            SpendCondition(
+                [
+                    LockPrimitive::Pkh(Pkh::single(public_key.hash())),
+                    LockPrimitive::Tim(LockTim::coinbase()),
+                ]
+                .into(),
+            )
+            .into(),
+            0,
+        );
+
+        let single = LockRoot::Lock(
+            SpendCondition::new_pkh(Pkh::single(
+                "2nEFkqYm51yfqsYgfRx72w8FF9bmWqnkJu8XqY8T7psXufjYNRxf5ME"
+                    .try_into()
+                    .unwrap(),
+            ))
+            .into(),
+        );
+        let multisig = LockRoot::Lock(
+            SpendCondition::new_pkh(Pkh::new(
+                2,
+                vec![
+                    "2nEFkqYm51yfqsYgfRx72w8FF9bmWqnkJu8XqY8T7psXufjYNRxf5ME"
+                        .try_into()
+                        .unwrap(),
+                    "6psXufjYNRxffRx72w8FF9b5MYg8TEmWq2nEFkqYm51yfqsnkJu8XqX"
+                        .try_into()
+                        .unwrap(),
+                ],
+            ))
+            .into(),
+        );
+        let hash = LockRoot::Hash(
+            "6psXufjYNRxffRx72w8FF9b5MYg8TEmWq2nEFkqYm51yfqsnkJu8XqX"
+                .try_into()
+                .unwrap(),
+        );
+        let recipients = vec![
+            (single.clone(), Nicks(1000)),
+            (multisig.clone(), Nicks(2500)),
+            (hash.clone(), Nicks(1500)),
+            (LockRoot::Hash(single.hash()), Nicks(500)),
+        ];
+
+        let mut builder = TxBuilder::new(TxEngineSettings::v1_with_word_cost(Nicks(8)));
+        builder
+            .multi_spend(
+                notes
+                    .iter()
+                    .map(|n| (n.clone(), Some(spend_condition.clone())))
+                    .collect(),
+                recipients,
+                public_key.hash(),
+                true,
+            )
+            .unwrap()
+            .sign(&private_key)
+            .validate()
+            .unwrap();
+
+        assert_eq!(builder.cur_fee(), builder.calc_fee());
+
+        let mut totals = BTreeMap::<Digest, Nicks>::new();
+        for spend in builder.all_spends().values() {
+            let seeds = &spend.spend.seeds().0;
+            let roots = seeds
+                .iter()
+                .map(|s| s.lock_root.hash())
+                .collect::<BTreeSet<_>>();
+            // Seeds sharing a lock root are merged
+            assert_eq!(roots.len(), seeds.len());
+            for seed in seeds.iter() {
+                *totals.entry(seed.lock_root.hash()).or_insert(Nicks(0)) += seed.gift;
+            }
+        }
+        assert_eq!(totals[&single.hash()], Nicks(1500));
+        assert_eq!(totals[&multisig.hash()], Nicks(2500));
+        assert_eq!(totals[&hash.hash()], Nicks(1500));
+
+        let refund = totals
+            .get(
+                &LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(public_key.hash())).into())
+                    .hash(),
+            )
+            .copied()
+            .unwrap_or(Nicks(0));
+        assert_eq!(Nicks(5500) + builder.cur_fee() + refund, Nicks(9000));
+
+        assert!(matches!(
+            TxBuilder::new(TxEngineSettings::v1_with_word_cost(Nicks(8))).multi_spend(
+                notes
+                    .iter()
+                    .map(|n| (n.clone(), Some(spend_condition.clone())))
+                    .collect(),
+                vec![(single.clone(), Nicks(9000))],
+                public_key.hash(),
+                false,
+            ),
+            Err(BuildError::InsufficientFunds)
+        ));
+
+        assert!(matches!(
+            TxBuilder::new(TxEngineSettings::v1_with_word_cost(Nicks(8))).multi_spend(
+                notes
+                    .iter()
+                    .map(|n| (n.clone(), Some(spend_condition.clone())))
+                    .collect(),
+                vec![(single.clone(), Nicks(u64::MAX)), (single, Nicks(1))],
+                public_key.hash(),
+                false,
+            ),
+            Err(BuildError::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_pay_to_self() {
+        let (_, public_key) = keys();
+        let note = Note::V1(v1::NoteV1 {
+            version: Version::V1,
+            origin_page: 13,
+            name: Name::new(
+                "2H7WHTE9dFXiGgx4J432DsCLuMovNkokfcnCGRg7utWGM9h13PgQvsH"
+                    .try_into()
+                    .unwrap(),
+                "7yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM"
+                    .try_into()
+                    .unwrap(),
+            ),
+            note_data: NoteData::empty(),
+            assets: Nicks(3000),
+        });
+        let spend_condition: (Lock, usize) = (
+            SpendCondition(
+                [
+                    LockPrimitive::Pkh(Pkh::single(public_key.hash())),
+                    LockPrimitive::Tim(LockTim::coinbase()),
+                ]
+                .into(),
+            )
+            .into(),
+            0,
+        );
+
+        let own = LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(public_key.hash())).into());
+        for recipient in [own.clone(), LockRoot::Hash(own.hash())] {
+            assert!(matches!(
+                TxBuilder::new(TxEngineSettings::v1_with_word_cost(Nicks(8))).multi_spend(
+                    vec![(note.clone(), Some(spend_condition.clone()))],
+                    vec![(recipient, Nicks(1000))],
+                    public_key.hash(),
+                    false,
+                ),
+                Err(BuildError::RecipientIsRefund)
+            ));
+        }
+    }
+
     #[test]
     fn test_first_name() {
         let (_, public_key) = keys();
@@ -1376,19 +1662,16 @@ mod tests {
                             .try_into()
                             .unwrap(),
                     )),
-                    LockPrimitive::Hax(
-                        Hax {
-                            preimages: [Digest([
-                                Belt(1730770831742798981),
-                                Belt(2676322185709933211),
-                                Belt(8329210750824781744),
-                                Belt(16756092452590401876),
-                                Belt(3547445316740171466),
-                            ])]
-                            .into(),
-                        }
+                    LockPrimitive::Hax(Hax {
+                        preimages: [Digest([
+                            Belt(1730770831742798981),
+                            Belt(2676322185709933211),
+                            Belt(8329210750824781744),
+                            Belt(16756092452590401876),
+                            Belt(3547445316740171466),
+                        ])]
                         .into(),
-                    ),
+                    }),
                 ]
                 .into(),
             )
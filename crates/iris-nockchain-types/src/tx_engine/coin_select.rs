@@ -0,0 +1,606 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use iris_ztd::Digest;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use alloc::{boxed::Box, format, string::ToString};
+
+use super::builder::{fee_for_spends, BuildError, SpendBuilder};
+use super::note::{Balance, Note};
+use super::v1::{Lock, LockRoot, Pkh, SpendCondition};
+use super::TxEngineSettings;
+use crate::Nicks;
+
+/// Upper bound on the number of branches explored by [`SelectionStrategy::BranchAndBound`].
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// How [`CoinSelector`] picks input notes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum SelectionStrategy {
+    /// Spend the biggest notes first.
+    #[default]
+    LargestFirst,
+    /// Spend the smallest notes first, consolidating dust.
+    SmallestFirst,
+    /// Search for a set of notes that covers gift and fee without a refund. Falls back to
+    /// `LargestFirst` if no such set is found.
+    BranchAndBound,
+    /// Use as few notes as possible, preferring the smallest note that completes the set.
+    FewestInputs,
+}
+
+/// Notes picked by [`CoinSelector::select`], ready to be passed to [`TxBuilder::simple_spend`].
+///
+/// [`TxBuilder::simple_spend`]: super::TxBuilder::simple_spend
+#[derive(Clone, Debug)]
+pub struct CoinSelection {
+    pub inputs: Vec<(Note, Option<(Lock, usize)>)>,
+    /// Estimated fee of spending the inputs.
+    pub fee: Nicks,
+    /// Estimated refund. Zero if the inputs are consumed without change.
+    pub change: Nicks,
+}
+
+#[derive(Clone, Debug)]
+struct Candidate {
+    note: Note,
+    spend_condition: Option<(Lock, usize)>,
+    /// Spend with the gift seed only.
+    exact: SpendBuilder,
+    /// Spend with both the gift and the refund seed.
+    change: SpendBuilder,
+}
+
+impl Candidate {
+    /// Fee of `spend` on its own, before clamping to the minimum fee.
+    fn cost(spend: &SpendBuilder, settings: &TxEngineSettings) -> Nicks {
+        spend.spend.unclamped_fee(settings) + spend.missing_unlocks_fee(settings)
+    }
+}
+
+/// Picks input notes from a [`Balance`] to cover a gift, counting the fee as it goes with the
+/// same rule as [`TxBuilder::calc_fee`](super::TxBuilder::calc_fee).
+#[derive(Clone, Debug)]
+pub struct CoinSelector {
+    settings: TxEngineSettings,
+    strategy: SelectionStrategy,
+    include_lock_data: bool,
+}
+
+impl CoinSelector {
+    pub fn new(settings: TxEngineSettings, strategy: SelectionStrategy) -> Self {
+        Self {
+            settings,
+            strategy,
+            include_lock_data: false,
+        }
+    }
+
+    /// Account for lock data in the seeds' note data, same as `include_lock_data` in
+    /// [`TxBuilder::simple_spend`](super::TxBuilder::simple_spend).
+    pub fn include_lock_data(&mut self, include_lock_data: bool) -> &mut Self {
+        self.include_lock_data = include_lock_data;
+        self
+    }
+
+    /// Select notes to pay `gift` to `recipient`, refunding to `refund_pkh`.
+    ///
+    /// `spend_condition` resolves the lock and spend condition index of a V1 note. V1 notes it
+    /// returns `None` for are considered unspendable and skipped.
+    pub fn select(
+        &self,
+        balance: &Balance,
+        gift: Nicks,
+        recipient: Digest,
+        refund_pkh: Digest,
+        spend_condition: impl Fn(&Note) -> Option<(Lock, usize)>,
+    ) -> Result<CoinSelection, BuildError> {
+        if gift == 0 {
+            return Err(BuildError::ZeroGift);
+        }
+
+        let recipient = LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(recipient)).into());
+        let refund = LockRoot::Lock(SpendCondition::new_pkh(Pkh::single(refund_pkh)).into());
+
+        let mut candidates = vec![];
+        for (_, note) in balance.0.iter() {
+            if note.assets() == 0 {
+                continue;
+            }
+            let sc = match note {
+                Note::V0(_) => None,
+                Note::V1(_) => match spend_condition(note) {
+                    Some(sc) => Some(sc),
+                    None => continue,
+                },
+            };
+            candidates.push(self.candidate(note, sc, &recipient, &refund)?);
+        }
+
+        // Ascending by assets, ties broken by name, so that selection is deterministic.
+        candidates.sort_by(|a, b| {
+            a.note
+                .assets()
+                .cmp(&b.note.assets())
+                .then_with(|| a.note.name().cmp(&b.note.name()))
+        });
+
+        let selection = match self.strategy {
+            SelectionStrategy::LargestFirst => self.accumulate(candidates.iter().rev(), gift)?,
+            SelectionStrategy::SmallestFirst => self.accumulate(candidates.iter(), gift)?,
+            SelectionStrategy::BranchAndBound => match self.branch_and_bound(&candidates, gift)? {
+                Some(selection) => Some(selection),
+                None => self.accumulate(candidates.iter().rev(), gift)?,
+            },
+            SelectionStrategy::FewestInputs => self.fewest_inputs(&candidates, gift)?,
+        };
+
+        selection.ok_or(BuildError::InsufficientFunds)
+    }
+
+    fn candidate(
+        &self,
+        note: &Note,
+        spend_condition: Option<(Lock, usize)>,
+        recipient: &LockRoot,
+        refund: &LockRoot,
+    ) -> Result<Candidate, BuildError> {
+        let mut exact = SpendBuilder::new(note.clone(), spend_condition.clone(), None)?;
+        let seed = exact.build_seed(recipient.clone(), note.assets(), self.include_lock_data);
+        exact.seed(seed);
+
+        let mut change =
+            SpendBuilder::new(note.clone(), spend_condition.clone(), Some(refund.clone()))?;
+        let seed = change.build_seed(recipient.clone(), Nicks(1), self.include_lock_data);
+        change.seed(seed).compute_refund(self.include_lock_data);
+
+        Ok(Candidate {
+            note: note.clone(),
+            spend_condition,
+            exact,
+            change,
+        })
+    }
+
+    /// Fee of spending `picked`, computed the same way as [`TxBuilder::calc_fee`].
+    ///
+    /// This is deliberately not [`SpendsV1::fee_for_many`], which does not discount witness
+    /// words and ignores signatures that are still missing, so it would disagree with the fee
+    /// the builder sets once the selection is spent.
+    ///
+    /// [`TxBuilder::calc_fee`]: super::TxBuilder::calc_fee
+    /// [`SpendsV1::fee_for_many`]: super::v1::SpendsV1::fee_for_many
+    fn fee(&self, picked: &[&Candidate], with_change: bool) -> Nicks {
+        // Only one of the inputs carries the refund
+        let spends = picked.iter().enumerate().map(|(i, c)| {
+            if with_change && i == 0 {
+                &c.change
+            } else {
+                &c.exact
+            }
+        });
+        fee_for_spends(spends, &self.settings)
+    }
+
+    /// Turn `picked` into a selection, or `None` if it does not cover gift and fee.
+    fn finish(
+        &self,
+        picked: &[&Candidate],
+        gift: Nicks,
+    ) -> Result<Option<CoinSelection>, BuildError> {
+        let total = picked
+            .iter()
+            .try_fold(Nicks(0), |acc, c| acc.checked_add(c.note.assets()))
+            .ok_or(BuildError::AmountOverflow)?;
+
+        let fee = self.fee(picked, true);
+        let needed = gift.checked_add(fee).ok_or(BuildError::AmountOverflow)?;
+        let (fee, change) = if total > needed {
+            (fee, total - needed)
+        } else {
+            // Leftover is worth less than the refund seed, so it goes to the fee.
+            let fee = self.fee(picked, false);
+            let needed = gift.checked_add(fee).ok_or(BuildError::AmountOverflow)?;
+            if total < needed {
+                return Ok(None);
+            }
+            (total - gift, Nicks(0))
+        };
+
+        Ok(Some(CoinSelection {
+            inputs: picked
+                .iter()
+                .map(|c| (c.note.clone(), c.spend_condition.clone()))
+                .collect(),
+            fee,
+            change,
+        }))
+    }
+
+    fn accumulate<'a>(
+        &self,
+        candidates: impl Iterator<Item = &'a Candidate>,
+        gift: Nicks,
+    ) -> Result<Option<CoinSelection>, BuildError> {
+        let mut picked = vec![];
+        for c in candidates {
+            picked.push(c);
+            if let Some(selection) = self.finish(&picked, gift)? {
+                return Ok(Some(selection));
+            }
+        }
+        Ok(None)
+    }
+
+    fn fewest_inputs(
+        &self,
+        candidates: &[Candidate],
+        gift: Nicks,
+    ) -> Result<Option<CoinSelection>, BuildError> {
+        let Some(greedy) = self.accumulate(candidates.iter().rev(), gift)? else {
+            return Ok(None);
+        };
+        let count = greedy.inputs.len();
+
+        // Keep the largest count - 1 notes, and complete the set with the smallest note that
+        // still covers the gift, so that the refund is as small as possible.
+        let (rest, largest) = candidates.split_at(candidates.len() - (count - 1));
+        let mut picked: Vec<&Candidate> = largest.iter().rev().collect();
+        for c in rest {
+            picked.push(c);
+            if let Some(selection) = self.finish(&picked, gift)? {
+                return Ok(Some(selection));
+            }
+            picked.pop();
+        }
+
+        Ok(Some(greedy))
+    }
+
+    fn branch_and_bound(
+        &self,
+        candidates: &[Candidate],
+        gift: Nicks,
+    ) -> Result<Option<CoinSelection>, BuildError> {
+        // Effective value is what a note contributes to the gift after paying for its own spend.
+        let mut pool: Vec<(&Candidate, u64)> = candidates
+            .iter()
+            .filter_map(|c| {
+                let value = c
+                    .note
+                    .assets()
+                    .0
+                    .checked_sub(Candidate::cost(&c.exact, &self.settings).0)?;
+                (value > 0).then_some((c, value))
+            })
+            .collect();
+        pool.sort_by(|a, b| b.1.cmp(&a.1));
+
+        // Overpaying the fee by less than what a refund seed costs beats creating the refund.
+        let Some(change_cost) = candidates
+            .iter()
+            .map(|c| {
+                Candidate::cost(&c.change, &self.settings)
+                    .saturating_sub(Candidate::cost(&c.exact, &self.settings))
+                    .0
+            })
+            .max()
+        else {
+            return Ok(None);
+        };
+
+        // Effective values are estimates, so keep searching until a set needs no refund
+        let values: Vec<u64> = pool.iter().map(|(_, v)| *v).collect();
+        let mut selected = vec![];
+        let mut tries = BNB_MAX_TRIES;
+        let mut selection = None;
+        let mut error = None;
+        bnb_search(
+            &values,
+            0,
+            0,
+            values.iter().fold(0u64, |acc, v| acc.saturating_add(*v)),
+            gift.0,
+            gift.0.saturating_add(change_cost),
+            &mut selected,
+            &mut tries,
+            &mut |selected| {
+                let picked: Vec<&Candidate> = selected.iter().map(|&i| pool[i].0).collect();
+                match self.finish(&picked, gift) {
+                    Ok(Some(s)) if s.change == 0 => {
+                        selection = Some(s);
+                        true
+                    }
+                    Ok(_) => false,
+                    Err(e) => {
+                        error = Some(e);
+                        true
+                    }
+                }
+            },
+        );
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(selection),
+        }
+    }
+}
+
+/// Depth-first search for a subset of `values` summing to between `target` and `upper`.
+///
+/// Every such subset is passed to `accept`, and the search stops once it returns `true`.
+#[allow(clippy::too_many_arguments)]
+fn bnb_search(
+    values: &[u64],
+    idx: usize,
+    current: u64,
+    remaining: u64,
+    target: u64,
+    upper: u64,
+    selected: &mut Vec<usize>,
+    tries: &mut usize,
+    accept: &mut impl FnMut(&[usize]) -> bool,
+) -> bool {
+    if *tries == 0 || current > upper {
+        return false;
+    }
+    *tries -= 1;
+
+    if current >= target {
+        return accept(selected);
+    }
+    if idx == values.len() || current.saturating_add(remaining) < target {
+        return false;
+    }
+
+    let value = values[idx];
+    let remaining = remaining - value;
+
+    selected.push(idx);
+    if bnb_search(
+        values,
+        idx + 1,
+        current.saturating_add(value),
+        remaining,
+        target,
+        upper,
+        selected,
+        tries,
+        accept,
+    ) {
+        return true;
+    }
+    selected.pop();
+
+    bnb_search(
+        values,
+        idx + 1,
+        current,
+        remaining,
+        target,
+        upper,
+        selected,
+        tries,
+        accept,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::{LockPrimitive, LockTim, NoteData, NoteV1};
+    use crate::{Name, TxBuilder, Version};
+    use bip39::Mnemonic;
+    use iris_crypto::{derive_master_key, PrivateKey};
+    use iris_ztd::{Hashable, ZMap};
+
+    fn keys() -> PrivateKey {
+        let mnemonic = Mnemonic::parse("dice domain inspire horse time initial monitor nature mass impose tone benefit vibrant dash kiss mosquito rice then color ribbon agent method drop fat").unwrap();
+        derive_master_key(&mnemonic.to_seed(""))
+            .private_key
+            .unwrap()
+    }
+
+    fn spend_condition(private_key: &PrivateKey) -> (Lock, usize) {
+        (
+            SpendCondition(
+                [
+                    LockPrimitive::Pkh(Pkh::single(private_key.public_key().hash())),
+                    LockPrimitive::Tim(LockTim::coinbase()),
+                ]
+                .into(),
+            )
+            .into(),
+            0,
+        )
+    }
+
+    fn balance(assets: &[u64]) -> Balance {
+        let lasts = [
+            "7yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+            "6yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+            "5yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+            "4yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM",
+        ];
+        Balance(
+            assets
+                .iter()
+                .zip(lasts)
+                .enumerate()
+                .map(|(i, (assets, last))| {
+                    let name = Name::new(
+                        "2H7WHTE9dFXiGgx4J432DsCLuMovNkokfcnCGRg7utWGM9h13PgQvsH"
+                            .try_into()
+                            .unwrap(),
+                        last.try_into().unwrap(),
+                    );
+                    let note = Note::V1(NoteV1 {
+                        version: Version::V1,
+                        origin_page: 13 + i as u32,
+                        name,
+                        note_data: NoteData::empty(),
+                        assets: Nicks(*assets),
+                    });
+                    (name, note)
+                })
+                .collect::<ZMap<_, _>>(),
+        )
+    }
+
+    fn select(
+        strategy: SelectionStrategy,
+        balance: &Balance,
+        gift: u64,
+    ) -> Result<CoinSelection, BuildError> {
+        let private_key = keys();
+        let sc = spend_condition(&private_key);
+        CoinSelector::new(TxEngineSettings::v1_with_word_cost(Nicks(8)), strategy).select(
+            balance,
+            Nicks(gift),
+            "2nEFkqYm51yfqsYgfRx72w8FF9bmWqnkJu8XqY8T7psXufjYNRxf5ME"
+                .try_into()
+                .unwrap(),
+            private_key.public_key().hash(),
+            |_| Some(sc.clone()),
+        )
+    }
+
+    fn picked(selection: &CoinSelection) -> Vec<u64> {
+        selection.inputs.iter().map(|(n, _)| n.assets().0).collect()
+    }
+
+    #[test]
+    fn test_greedy_strategies() {
+        let balance = balance(&[1000, 3000, 5000, 2000]);
+
+        let selection = select(SelectionStrategy::LargestFirst, &balance, 2500).unwrap();
+        assert_eq!(picked(&selection), [5000]);
+        assert_eq!(selection.fee + selection.change + 2500, Nicks(5000));
+
+        let selection = select(SelectionStrategy::SmallestFirst, &balance, 2500).unwrap();
+        assert_eq!(picked(&selection), [1000, 2000, 3000]);
+
+        let selection = select(SelectionStrategy::FewestInputs, &balance, 2000).unwrap();
+        assert_eq!(picked(&selection), [3000]);
+
+        let selection = select(SelectionStrategy::FewestInputs, &balance, 7000).unwrap();
+        assert_eq!(picked(&selection), [5000, 3000]);
+
+        assert!(matches!(
+            select(SelectionStrategy::LargestFirst, &balance, 11000),
+            Err(BuildError::InsufficientFunds)
+        ));
+        assert!(matches!(
+            select(SelectionStrategy::LargestFirst, &balance, 0),
+            Err(BuildError::ZeroGift)
+        ));
+        assert!(matches!(
+            select(SelectionStrategy::LargestFirst, &balance, u64::MAX),
+            Err(BuildError::AmountOverflow)
+        ));
+    }
+
+    #[test]
+    fn test_bnb_search_skips_rejected_sets() {
+        // [4, 2] is in range and found first, but only the later [4, 1] is accepted
+        let values = [4, 3, 2, 1];
+        let mut selected = vec![];
+        let mut tries = BNB_MAX_TRIES;
+        let mut seen = vec![];
+        let found = bnb_search(
+            &values,
+            0,
+            0,
+            values.iter().sum(),
+            5,
+            6,
+            &mut selected,
+            &mut tries,
+            &mut |selected| {
+                seen.push(selected.to_vec());
+                selected.iter().map(|&i| values[i]).sum::<u64>() == 5
+            },
+        );
+        assert!(found);
+        assert_eq!(seen, [vec![0, 2], vec![0, 3]]);
+        assert_eq!(selected, [0, 3]);
+    }
+
+    #[test]
+    fn test_branch_and_bound_exact() {
+        let balance = balance(&[1000, 3000, 5000, 2000]);
+
+        let private_key = keys();
+        let selector = CoinSelector::new(
+            TxEngineSettings::v1_with_word_cost(Nicks(8)),
+            SelectionStrategy::BranchAndBound,
+        );
+        let recipient = LockRoot::Lock(
+            SpendCondition::new_pkh(Pkh::single(private_key.public_key().hash())).into(),
+        );
+        let candidates = balance
+            .0
+            .iter()
+            .filter(|(_, n)| n.assets() == 3000 || n.assets() == 1000)
+            .map(|(_, n)| {
+                selector
+                    .candidate(
+                        n,
+                        Some(spend_condition(&private_key)),
+                        &recipient,
+                        &recipient,
+                    )
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        // Pick a gift that spending 3000 and 1000 covers exactly, without a refund
+        let fee = selector.fee(&candidates.iter().collect::<Vec<_>>(), false);
+        let gift = 4000 - fee.0;
+
+        let selection = select(SelectionStrategy::BranchAndBound, &balance, gift).unwrap();
+        let mut assets = picked(&selection);
+        assets.sort();
+        assert_eq!(assets, [1000, 3000]);
+        assert_eq!(selection.change, Nicks(0));
+        assert_eq!(selection.fee, fee);
+
+        let largest = select(SelectionStrategy::LargestFirst, &balance, gift).unwrap();
+        assert_eq!(picked(&largest), [5000]);
+        assert!(largest.change > 0);
+    }
+
+    #[test]
+    fn test_selection_builds() {
+        let private_key = keys();
+        let balance = balance(&[1000, 3000, 5000, 2000]);
+        let settings = TxEngineSettings::v1_with_word_cost(Nicks(8));
+        for strategy in [
+            SelectionStrategy::LargestFirst,
+            SelectionStrategy::SmallestFirst,
+            SelectionStrategy::BranchAndBound,
+            SelectionStrategy::FewestInputs,
+        ] {
+            let selection = select(strategy, &balance, 4500).unwrap();
+            let mut builder = TxBuilder::new(settings);
+            builder
+                .simple_spend(
+                    selection.inputs,
+                    "2nEFkqYm51yfqsYgfRx72w8FF9bmWqnkJu8XqY8T7psXufjYNRxf5ME"
+                        .try_into()
+                        .unwrap(),
+                    Nicks(4500),
+                    private_key.public_key().hash(),
+                    false,
+                )
+                .unwrap()
+                .sign(&private_key)
+                .validate()
+                .unwrap();
+            assert!(builder.cur_fee() <= selection.fee, "{strategy:?}");
+        }
+    }
+}
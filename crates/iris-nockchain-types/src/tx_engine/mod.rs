@@ -1,9 +1,17 @@
 pub mod builder;
+pub mod coin_select;
 pub mod note;
+pub mod note_data;
+pub mod pstx;
 pub mod tx;
 pub mod v0;
 pub mod v1;
+pub mod verify;
 
 pub use builder::*;
+pub use coin_select::*;
 pub use note::*;
+pub use note_data::*;
+pub use pstx::*;
 pub use tx::*;
+pub use verify::*;
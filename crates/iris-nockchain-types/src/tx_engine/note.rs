@@ -61,6 +61,10 @@ impl<'de> Deserialize<'de> for Nicks {
 }
 
 impl Nicks {
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
     pub fn saturating_sub(self, other: Self) -> Self {
         Self(self.0.saturating_sub(other.0))
     }
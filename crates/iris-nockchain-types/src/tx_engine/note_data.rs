@@ -0,0 +1,235 @@
+//! Typed access to [`NoteData`] entries.
+//!
+//! `NoteData` is an untyped `ZMap<String, Noun>`. A [`NoteDataKey`] ties a key to the noun
+//! schema of its value, so entries can be read and written without hand-rolled noun surgery:
+//!
+//! ```
+//! # use iris_nockchain_types::{NoteDataKey, v1::NoteData};
+//! # use iris_ztd::{NounDecode, NounEncode};
+//! #[derive(NounEncode, NounDecode, Debug, PartialEq)]
+//! struct Memo(String);
+//!
+//! impl NoteDataKey for Memo {
+//!     const KEY: &'static str = "memo";
+//! }
+//!
+//! let mut data = NoteData::empty();
+//! data.insert(&Memo("rent".into()));
+//! assert_eq!(data.get::<Memo>().unwrap(), Some(Memo("rent".into())));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use iris_ztd::{Noun, NounDecode, NounEncode};
+
+use super::v1::{Lock, LockPrimitive, NoteData, Pkh, SpendCondition};
+
+/// A typed [`NoteData`] entry, stored under [`NoteDataKey::KEY`].
+pub trait NoteDataKey: NounEncode + NounDecode {
+    const KEY: &'static str;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteDataError {
+    /// The value under `key` does not match the expected schema.
+    Decode { key: String },
+}
+
+impl core::fmt::Display for NoteDataError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NoteDataError::Decode { key } => {
+                write!(f, "Note data under key %{key} does not match its schema")
+            }
+        }
+    }
+}
+
+impl core::error::Error for NoteDataError {}
+
+impl NoteData {
+    /// Read and decode the entry for `T`, if present.
+    pub fn get<T: NoteDataKey>(&self) -> Result<Option<T>, NoteDataError> {
+        let Some((_, noun)) = self.0.get_key_value(&T::KEY.to_string()) else {
+            return Ok(None);
+        };
+        T::from_noun(noun)
+            .map(Some)
+            .ok_or_else(|| NoteDataError::Decode {
+                key: T::KEY.to_string(),
+            })
+    }
+
+    /// Encode and store the entry for `T`, replacing any previous value under the same key.
+    pub fn insert<T: NoteDataKey>(&mut self, value: &T) {
+        let key = T::KEY.to_string();
+        self.0.remove(&key);
+        self.0.insert(key, value.to_noun());
+    }
+
+    /// Remove the entry under `T::KEY`, returning the raw noun.
+    pub fn remove<T: NoteDataKey>(&mut self) -> Option<Noun> {
+        self.0.remove(&T::KEY.to_string())
+    }
+}
+
+/// Lock of the note, stored under `%lock` as `[%0 lock]`.
+#[derive(Debug, Clone)]
+pub struct NoteLock(pub Lock);
+
+impl NoteDataKey for NoteLock {
+    const KEY: &'static str = "lock";
+}
+
+impl NounEncode for NoteLock {
+    fn to_noun(&self) -> Noun {
+        (0, &self.0).to_noun()
+    }
+}
+
+impl NounDecode for NoteLock {
+    fn from_noun(noun: &Noun) -> Option<Self> {
+        let (version, lock): (u64, Lock) = NounDecode::from_noun(noun)?;
+        (version == 0).then_some(Self(lock))
+    }
+}
+
+/// Single-pkh shorthand for [`NoteLock`]; shares the `%lock` key.
+///
+/// Only decodes locks consisting of a single spend condition with a single `%pkh` primitive.
+#[derive(Debug, Clone)]
+pub struct NotePkh(pub Pkh);
+
+impl NoteDataKey for NotePkh {
+    const KEY: &'static str = "lock";
+}
+
+impl NounEncode for NotePkh {
+    fn to_noun(&self) -> Noun {
+        NoteLock(SpendCondition::new_pkh(self.0.clone()).into()).to_noun()
+    }
+}
+
+impl NounDecode for NotePkh {
+    fn from_noun(noun: &Noun) -> Option<Self> {
+        match NoteLock::from_noun(noun)?.0 {
+            Lock::Single(SpendCondition(prims)) => match &prims[..] {
+                [LockPrimitive::Pkh(pkh)] => Some(Self(pkh.clone())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+type SchemaCheck = fn(&Noun) -> bool;
+
+/// Set of known [`NoteDataKey`]s, used to validate untyped note data.
+///
+/// Starts out with the built-in `%lock` schema; apps register their own keys on top.
+#[derive(Debug, Clone)]
+pub struct NoteDataRegistry {
+    schemas: BTreeMap<&'static str, SchemaCheck>,
+}
+
+impl Default for NoteDataRegistry {
+    fn default() -> Self {
+        let mut ret = Self {
+            schemas: BTreeMap::new(),
+        };
+        ret.register::<NoteLock>();
+        ret
+    }
+}
+
+impl NoteDataRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `T`'s schema, replacing any schema previously registered for `T::KEY`.
+    pub fn register<T: NoteDataKey>(&mut self) -> &mut Self {
+        self.schemas
+            .insert(T::KEY, |noun| T::from_noun(noun).is_some());
+        self
+    }
+
+    pub fn is_registered(&self, key: &str) -> bool {
+        self.schemas.contains_key(key)
+    }
+
+    /// Check that every registered key present in `data` matches its schema.
+    ///
+    /// Unregistered keys are ignored.
+    pub fn validate(&self, data: &NoteData) -> Result<(), NoteDataError> {
+        for (key, noun) in &data.0 {
+            if let Some(check) = self.schemas.get(key.as_str()) {
+                if !check(noun) {
+                    return Err(NoteDataError::Decode { key: key.clone() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iris_ztd::{Hashable, ZMap};
+
+    #[derive(Debug, PartialEq, NounEncode, NounDecode)]
+    struct InvoiceId(u64);
+
+    impl NoteDataKey for InvoiceId {
+        const KEY: &'static str = "invoice";
+    }
+
+    #[test]
+    fn test_typed_entries() {
+        let pkh = Pkh::single(1u64.hash());
+        let mut data = NoteData::empty();
+        assert_eq!(data.get::<NotePkh>().unwrap().map(|p| p.0.m), None);
+
+        // Typed insert matches the legacy encoding
+        data.insert(&NotePkh(pkh.clone()));
+        assert_eq!(
+            data.0,
+            ZMap::from([("lock".to_string(), (0, ("pkh", &pkh), 0).to_noun())])
+        );
+        assert_eq!(data.get::<NotePkh>().unwrap().unwrap().0.hashes, pkh.hashes);
+        assert!(data.get::<NoteLock>().unwrap().is_some());
+
+        data.insert(&InvoiceId(7));
+        data.insert(&InvoiceId(8));
+        assert_eq!(data.get::<InvoiceId>().unwrap(), Some(InvoiceId(8)));
+        assert!(data.remove::<InvoiceId>().is_some());
+        assert_eq!(data.get::<InvoiceId>().unwrap(), None);
+
+        // Schema mismatch
+        data.0.insert("invoice".to_string(), (1u64, 2u64).to_noun());
+        assert_eq!(
+            data.get::<InvoiceId>().unwrap_err(),
+            NoteDataError::Decode {
+                key: "invoice".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_registry_validate() {
+        let mut data = NoteData::from_pkh(Pkh::single(1u64.hash()));
+        data.0.insert("invoice".to_string(), ("oops", 1).to_noun());
+
+        let mut registry = NoteDataRegistry::new();
+        assert!(registry.is_registered("lock"));
+        assert!(registry.validate(&data).is_ok());
+
+        registry.register::<InvoiceId>();
+        assert!(registry.validate(&data).is_err());
+
+        let mut data = NoteData::empty();
+        data.0.insert("lock".to_string(), (1, 2).to_noun());
+        assert!(NoteDataRegistry::new().validate(&data).is_err());
+    }
+}
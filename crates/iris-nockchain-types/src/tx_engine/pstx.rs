@@ -0,0 +1,457 @@
+//! Partially signed transactions (PSTX).
+//!
+//! A PSTX is a jammed `[%pstx version pstx]` envelope carrying everything a co-signer needs to
+//! review and sign a transaction built by someone else: the spends, each input's note and
+//! spend condition, the transaction display and the engine settings. Signatures collected by
+//! different signers are combined with [`Pstx::merge`].
+
+use alloc::vec::Vec;
+use iris_ztd::{cue_with_limits, jam, CueError, CueLimits, Hashable, NounDecode, NounEncode, ZMap};
+
+use super::note::{Name, Note};
+use super::v1::{Lock, LockRoot, SpendV1 as Spend, TransactionDisplay};
+use super::{BuildError, SpendBuilder, TxBuilder, TxEngineSettings};
+
+/// Current PSTX envelope version.
+pub const PSTX_VERSION: u64 = 1;
+
+const PSTX_TAG: &str = "pstx";
+
+#[derive(Debug, Clone, NounEncode, NounDecode)]
+pub struct PstxInput {
+    pub note: Note,
+    /// Lock of the note and index of the spend condition being used (V1 notes only).
+    pub spend_condition: Option<(Lock, usize)>,
+    pub refund_lock: Option<LockRoot>,
+    /// The spend, including any signatures collected so far.
+    pub spend: Spend,
+}
+
+#[derive(Debug, Clone, NounEncode, NounDecode)]
+pub struct Pstx {
+    pub settings: TxEngineSettings,
+    pub inputs: ZMap<Name, PstxInput>,
+    /// Display of the spends. Its output locks are restored into the seeds on import, and it
+    /// must match the display built from them.
+    pub display: TransactionDisplay,
+}
+
+#[derive(Debug)]
+pub enum PstxError {
+    Cue(CueError),
+    InvalidEnvelope,
+    UnsupportedVersion(u64),
+    /// The spend was not created from a note, so its input is unknown.
+    MissingInput(Name),
+    /// The PSTXs being merged do not describe the same transaction.
+    Mismatch,
+    /// A signature does not verify against the spend, or is filed under the wrong key hash.
+    InvalidSignature(Name),
+    /// A preimage does not hash to the digest it is filed under.
+    InvalidPreimage(Name),
+    /// The display does not match the one built from the spends.
+    InvalidDisplay,
+    Build(BuildError),
+}
+
+impl core::fmt::Display for PstxError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PstxError::Cue(e) => write!(f, "Unable to cue PSTX: {e}"),
+            PstxError::InvalidEnvelope => write!(f, "Not a valid PSTX envelope"),
+            PstxError::UnsupportedVersion(v) => write!(f, "Unsupported PSTX version {v}"),
+            PstxError::MissingInput(name) => {
+                write!(f, "Missing input note for [{} {}]", name.first, name.last)
+            }
+            PstxError::Mismatch => write!(f, "PSTXs describe different transactions"),
+            PstxError::InvalidSignature(name) => {
+                write!(f, "Invalid signature for [{} {}]", name.first, name.last)
+            }
+            PstxError::InvalidPreimage(name) => {
+                write!(f, "Invalid preimage for [{} {}]", name.first, name.last)
+            }
+            PstxError::InvalidDisplay => write!(f, "PSTX display does not match its spends"),
+            PstxError::Build(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl core::error::Error for PstxError {}
+
+impl From<CueError> for PstxError {
+    fn from(e: CueError) -> Self {
+        PstxError::Cue(e)
+    }
+}
+
+impl From<BuildError> for PstxError {
+    fn from(e: BuildError) -> Self {
+        PstxError::Build(e)
+    }
+}
+
+impl Pstx {
+    pub fn to_jam(&self) -> Vec<u8> {
+        jam((PSTX_TAG, PSTX_VERSION, self).to_noun())
+    }
+
+    pub fn from_jam(bytes: &[u8]) -> Result<Self, PstxError> {
+        let noun = cue_with_limits(bytes, &CueLimits::default())?;
+        let (tag, version, body): (iris_ztd::Noun, u64, iris_ztd::Noun) =
+            NounDecode::from_noun(&noun).ok_or(PstxError::InvalidEnvelope)?;
+        if tag != PSTX_TAG.to_noun() {
+            return Err(PstxError::InvalidEnvelope);
+        }
+        if version != PSTX_VERSION {
+            return Err(PstxError::UnsupportedVersion(version));
+        }
+        Self::from_noun(&body).ok_or(PstxError::InvalidEnvelope)
+    }
+
+    /// Union the signatures and preimages of `other` into this PSTX.
+    ///
+    /// Both must describe the same spends, i.e. have equal signature hashes and spend conditions.
+    /// Both are checked as in [`TxBuilder::from_pstx`] first, and entries already present in this
+    /// PSTX are kept.
+    pub fn merge(&mut self, other: &Pstx) -> Result<&mut Self, PstxError> {
+        if self.inputs.len() != other.inputs.len() {
+            return Err(PstxError::Mismatch);
+        }
+        TxBuilder::from_pstx(self.clone())?;
+        TxBuilder::from_pstx(other.clone())?;
+
+        let mut merged = ZMap::new();
+        for (name, input) in self.inputs.iter() {
+            let (_, theirs) = other
+                .inputs
+                .get_key_value(name)
+                .ok_or(PstxError::Mismatch)?;
+            if input.spend.sig_hash() != theirs.spend.sig_hash() {
+                return Err(PstxError::Mismatch);
+            }
+
+            let mut input = input.clone();
+            match (&mut input.spend, &theirs.spend) {
+                (Spend::S0(ours), Spend::S0(theirs)) => {
+                    for (pk, sig) in theirs.signature.0.iter() {
+                        if ours.signature.0.get_key_value(pk).is_none() {
+                            ours.signature.0.insert(*pk, *sig);
+                        }
+                    }
+                }
+                (Spend::S1(ours), Spend::S1(theirs)) => {
+                    let (ours, theirs) = (&mut ours.witness, &theirs.witness);
+                    if ours.lock_merkle_proof.hash() != theirs.lock_merkle_proof.hash() {
+                        return Err(PstxError::Mismatch);
+                    }
+                    for (pkh, sig) in theirs.pkh_signature.0.iter() {
+                        if ours.pkh_signature.0.get_key_value(pkh).is_none() {
+                            ours.pkh_signature.0.insert(*pkh, *sig);
+                        }
+                    }
+                    for (digest, preimage) in theirs.hax_map.iter() {
+                        if ours.hax_map.get_key_value(digest).is_none() {
+                            ours.hax_map.insert(*digest, preimage.clone());
+                        }
+                    }
+                }
+                _ => return Err(PstxError::Mismatch),
+            }
+            merged.insert(*name, input);
+        }
+
+        self.inputs = merged;
+        Ok(self)
+    }
+}
+
+/// Check that every signature of `spend` verifies against its signature hash under the key it is
+/// filed under, and that every preimage hashes to its digest.
+fn check_unlocks(name: &Name, spend: &Spend) -> Result<(), PstxError> {
+    let sig_hash = spend.sig_hash();
+    match spend {
+        Spend::S0(spend) => {
+            for (pk, sig) in spend.signature.0.iter() {
+                if !pk.verify(&sig_hash, sig) {
+                    return Err(PstxError::InvalidSignature(*name));
+                }
+            }
+        }
+        Spend::S1(spend) => {
+            for (pkh, (pk, sig)) in spend.witness.pkh_signature.0.iter() {
+                if *pkh != pk.hash() || !pk.verify(&sig_hash, sig) {
+                    return Err(PstxError::InvalidSignature(*name));
+                }
+            }
+            for (digest, preimage) in spend.witness.hax_map.iter() {
+                if preimage.hash() != *digest {
+                    return Err(PstxError::InvalidPreimage(*name));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl TxBuilder {
+    /// Snapshot this builder as a PSTX.
+    ///
+    /// Fails if any spend was not created from a note (e.g. via `from_raw_tx`). Unused fee pool
+    /// notes are not included.
+    pub fn to_pstx(&self) -> Result<Pstx, PstxError> {
+        let mut inputs = ZMap::new();
+        for (name, spend) in &self.spends {
+            let note = spend.note.clone().ok_or(PstxError::MissingInput(*name))?;
+            inputs.insert(
+                *name,
+                PstxInput {
+                    note,
+                    spend_condition: spend.spend_condition.clone(),
+                    refund_lock: spend.refund_lock.clone(),
+                    spend: spend.spend.clone(),
+                },
+            );
+        }
+        Ok(Pstx {
+            settings: self.settings,
+            inputs,
+            display: self.build().display,
+        })
+    }
+
+    /// Rebuild a builder from a PSTX, checking its signatures, preimages and display.
+    pub fn from_pstx(pstx: Pstx) -> Result<Self, PstxError> {
+        let Pstx {
+            settings,
+            inputs,
+            display,
+        } = pstx;
+        let mut builder = TxBuilder::new(settings);
+        for (name, input) in inputs {
+            if input.note.name() != name {
+                return Err(PstxError::InvalidEnvelope);
+            }
+            let mut spend =
+                SpendBuilder::new(input.note, input.spend_condition, input.refund_lock)?;
+            match (&spend.spend, &input.spend) {
+                (Spend::S0(_), Spend::S0(_)) => {}
+                (Spend::S1(ours), Spend::S1(theirs))
+                    if ours.witness.lock_merkle_proof.hash()
+                        == theirs.witness.lock_merkle_proof.hash() => {}
+                _ => return Err(BuildError::InvalidSpendCondition.into()),
+            }
+            check_unlocks(&name, &input.spend)?;
+            spend.spend = input.spend;
+            // Seeds only carry the lock root, so take full locks back from the display
+            let seeds = core::mem::take(&mut spend.spend.seeds_mut().0);
+            spend.spend.seeds_mut().0 = seeds
+                .into_iter()
+                .map(|mut seed| {
+                    let root = seed.lock_root.hash();
+                    if let Some((_, output)) = display.outputs.get_key_value(&root) {
+                        if output.lock.hash() == root {
+                            seed.lock_root = LockRoot::Lock(output.lock.clone());
+                        }
+                    }
+                    seed
+                })
+                .collect();
+            builder.spend(spend);
+        }
+        if builder.build().display.to_noun() != display.to_noun() {
+            return Err(PstxError::InvalidDisplay);
+        }
+        Ok(builder)
+    }
+
+    /// Export this builder as a jammed PSTX.
+    pub fn export_pstx(&self) -> Result<Vec<u8>, PstxError> {
+        Ok(self.to_pstx()?.to_jam())
+    }
+
+    /// Import a builder from a jammed PSTX, keeping any collected signatures.
+    pub fn import_pstx(bytes: &[u8]) -> Result<Self, PstxError> {
+        Self::from_pstx(Pstx::from_jam(bytes)?)
+    }
+}
+
+/// Merge several jammed PSTXs for the same transaction into one.
+pub fn merge_pstx<'a>(pstxs: impl IntoIterator<Item = &'a [u8]>) -> Result<Vec<u8>, PstxError> {
+    let mut pstxs = pstxs.into_iter();
+    let mut merged = Pstx::from_jam(pstxs.next().ok_or(PstxError::InvalidEnvelope)?)?;
+    for bytes in pstxs {
+        merged.merge(&Pstx::from_jam(bytes)?)?;
+    }
+    Ok(merged.to_jam())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::{NoteData, NoteV1, Pkh, SpendCondition, Witness};
+    use crate::{Nicks, Version};
+    use alloc::vec;
+    use iris_crypto::derive_master_key;
+
+    #[test]
+    fn test_pstx_cosign() {
+        let signers =
+            [[1u8; 64], [2u8; 64]].map(|seed| derive_master_key(&seed).private_key.unwrap());
+        let pkh = Pkh::new(2, signers.iter().map(|k| k.public_key().hash()).collect());
+        let note = Note::V1(NoteV1 {
+            version: Version::V1,
+            origin_page: 13,
+            name: Name::new(
+                "2H7WHTE9dFXiGgx4J432DsCLuMovNkokfcnCGRg7utWGM9h13PgQvsH"
+                    .try_into()
+                    .unwrap(),
+                "7yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM"
+                    .try_into()
+                    .unwrap(),
+            ),
+            note_data: NoteData::empty(),
+            assets: Nicks(100_000),
+        });
+        let lock: Lock = SpendCondition::new_pkh(pkh).into();
+
+        let mut builder = TxBuilder::new(TxEngineSettings::v1_with_word_cost(Nicks(8)));
+        builder
+            .simple_spend(
+                vec![(note, Some((lock, 0)))],
+                signers[0].public_key().hash(),
+                Nicks(50_000),
+                signers[1].public_key().hash(),
+                false,
+            )
+            .unwrap();
+        let unsigned = builder.export_pstx().unwrap();
+        let display = builder.build().display.to_noun();
+
+        // Each signer signs independently
+        let signed = signers
+            .iter()
+            .map(|key| {
+                let mut builder = TxBuilder::import_pstx(&unsigned).unwrap();
+                builder.sign(key);
+                builder.export_pstx().unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(TxBuilder::import_pstx(&signed[0])
+            .unwrap()
+            .validate()
+            .is_err());
+        let merged = merge_pstx(signed.iter().map(|v| &v[..])).unwrap();
+        let mut builder = TxBuilder::import_pstx(&merged).unwrap();
+        builder.validate().unwrap();
+        // Output locks survive the round trips through the envelope
+        assert_eq!(builder.build().display.to_noun(), display);
+        let sig_hashes = |b: &TxBuilder| {
+            b.all_spends()
+                .values()
+                .map(|s| s.spend.sig_hash())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sig_hashes(&builder),
+            sig_hashes(&TxBuilder::import_pstx(&unsigned).unwrap())
+        );
+
+        // Different transaction
+        let mut other = TxBuilder::import_pstx(&unsigned).unwrap();
+        other.recalc_and_set_fee(false).unwrap();
+        other
+            .set_fee_and_balance_refund(Nicks(10_000), false, false)
+            .unwrap();
+        assert!(matches!(
+            merge_pstx([&unsigned[..], &other.export_pstx().unwrap()[..]]),
+            Err(PstxError::Mismatch)
+        ));
+
+        // Forged or conflicting co-signers
+        let forge = |f: &dyn Fn(&mut Witness)| {
+            let mut pstx = Pstx::from_jam(&signed[1]).unwrap();
+            let name = *pstx.inputs.iter().next().unwrap().0;
+            let mut input = pstx.inputs.remove(&name).unwrap();
+            let Spend::S1(spend) = &mut input.spend else {
+                panic!("Expected a V1 spend");
+            };
+            f(&mut spend.witness);
+            pstx.inputs.insert(name, input);
+            pstx
+        };
+        let ours = Pstx::from_jam(&signed[0]).unwrap();
+        let pkhs = signers.each_ref().map(|k| k.public_key().hash());
+        let wrong_sig = signers[1].sign(&0u64.hash());
+        for forged in [
+            // Signature over something else
+            forge(&|w| {
+                w.pkh_signature.0 = [(pkhs[1], (signers[1].public_key(), wrong_sig))].into();
+            }),
+            // Valid signature filed under another key hash
+            forge(&|w| {
+                let entry = *w.pkh_signature.0.get_key_value(&pkhs[1]).unwrap().1;
+                w.pkh_signature.0 = [(pkhs[0], entry)].into();
+            }),
+        ] {
+            assert!(matches!(
+                ours.clone().merge(&forged),
+                Err(PstxError::InvalidSignature(_))
+            ));
+            assert!(matches!(
+                TxBuilder::from_pstx(forged),
+                Err(PstxError::InvalidSignature(_))
+            ));
+        }
+        let forged = forge(&|w| {
+            w.hax_map.insert(0u64.hash(), 1u64.to_noun());
+        });
+        assert!(matches!(
+            ours.clone().merge(&forged),
+            Err(PstxError::InvalidPreimage(_))
+        ));
+
+        // Entries already collected are never replaced
+        let mut merged = ours.clone();
+        merged.merge(&Pstx::from_jam(&signed[1]).unwrap()).unwrap();
+        let witness = |p: &Pstx| match &p.inputs.iter().next().unwrap().1.spend {
+            Spend::S1(spend) => spend.witness.clone(),
+            Spend::S0(_) => panic!("Expected a V1 spend"),
+        };
+        assert_eq!(
+            witness(&merged).pkh_signature.0.get_key_value(&pkhs[0]),
+            witness(&ours).pkh_signature.0.get_key_value(&pkhs[0])
+        );
+
+        // Witness for a different spend condition than the input's
+        let other_lock: Lock = SpendCondition::new_pkh(Pkh::single(pkhs[0])).into();
+        let forged = forge(&|w| {
+            w.lock_merkle_proof = Witness::new(other_lock.clone(), 0).lock_merkle_proof;
+            w.pkh_signature.0 = ZMap::new();
+        });
+        assert!(matches!(
+            TxBuilder::from_pstx(forged),
+            Err(PstxError::Build(BuildError::InvalidSpendCondition))
+        ));
+
+        // Display that does not match the spends
+        let mut forged = Pstx::from_jam(&signed[1]).unwrap();
+        forged
+            .display
+            .outputs
+            .insert(0u64.hash(), other_lock.clone().into());
+        assert!(matches!(
+            ours.clone().merge(&forged),
+            Err(PstxError::InvalidDisplay)
+        ));
+        assert!(matches!(
+            TxBuilder::from_pstx(forged),
+            Err(PstxError::InvalidDisplay)
+        ));
+
+        assert!(matches!(
+            Pstx::from_jam(&jam((PSTX_TAG, 9u64, 0u64).to_noun())),
+            Err(PstxError::UnsupportedVersion(9))
+        ));
+    }
+}
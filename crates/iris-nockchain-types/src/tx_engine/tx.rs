@@ -1,7 +1,7 @@
 use super::note::{BlockHeight, Name, Note, Version};
 use crate::Nicks;
 use alloc::vec::Vec;
-use iris_ztd::{Bignum, Digest, Noun, ZSet};
+use iris_ztd::{Bignum, Digest, Noun, NounDecode, NounEncode, ZSet};
 
 #[cfg(feature = "wasm")]
 use alloc::{boxed::Box, format, string::ToString};
@@ -170,7 +170,19 @@ impl RawTx {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    NounEncode,
+    NounDecode,
+)]
 #[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 #[cfg_attr(feature = "wasm", tsify(into_wasm_abi, from_wasm_abi))]
 pub struct TxEngineSettings {
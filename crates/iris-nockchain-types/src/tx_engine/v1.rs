@@ -102,12 +102,11 @@ impl NoteData {
 
 impl NoteData {
     pub fn push_lock(&mut self, lock: Lock) {
-        self.0.insert("lock".to_string(), (0, lock).to_noun());
+        self.insert(&super::NoteLock(lock));
     }
 
     pub fn push_pkh(&mut self, pkh: Pkh) {
-        self.0
-            .insert("lock".to_string(), (0, ("pkh", &pkh), 0).to_noun());
+        self.insert(&super::NotePkh(pkh));
     }
 }
 
@@ -1610,6 +1609,43 @@ mod tests {
         assert_eq!(outs, tx_outs);
     }
 
+    #[test]
+    fn check_zmap_ops_tx_id() {
+        // The tx id commits to the shape of the spends treap, as built by Hoon. Maps rebuilt with
+        // +uni, +del, +int, +dif and +bif must hash to the same id.
+        let noun = iris_ztd::cue(TX1).unwrap();
+        let (txid, spends): (String, Spends) = NounDecode::from_noun(&noun).unwrap();
+        let entries: Vec<(Name, Spend)> = spends.0.into_iter().collect();
+        let id = |spends: ZMap<Name, Spend>| RawTxV1::new(Spends(spends)).calc_id().to_string();
+
+        let evens: ZMap<_, _> = entries.iter().step_by(2).cloned().collect();
+        let odds: ZMap<_, _> = entries.iter().skip(1).step_by(2).cloned().collect();
+        let overlap: ZMap<_, _> = entries.iter().take(5).cloned().collect();
+        let all = evens.clone().union(odds.clone()).union(overlap);
+        assert_eq!(id(all.clone()), txid);
+        assert_eq!(id(odds.union(evens)), txid);
+
+        let extras: ZMap<_, _> = entries
+            .iter()
+            .take(7)
+            .map(|(name, spend)| (Name::new(name.last, name.first), spend.clone()))
+            .collect();
+        let mut more = all.clone().union(extras.clone());
+        assert_ne!(id(more.clone()), txid);
+        assert_eq!(id(more.clone().difference(extras.clone())), txid);
+        assert_eq!(id(more.clone().intersection(all.clone())), txid);
+        for (name, _) in extras {
+            more.remove(&name);
+        }
+        assert_eq!(id(more), txid);
+
+        let key = entries[entries.len() / 2].0;
+        let (left, found, right) = all.split(&key);
+        let mut joined = left.union(right);
+        joined.insert(key, found.unwrap());
+        assert_eq!(id(joined), txid);
+    }
+
     #[test]
     fn check_1padding() {
         let noun = iris_ztd::cue(TX2).unwrap();
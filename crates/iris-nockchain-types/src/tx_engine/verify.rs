@@ -0,0 +1,458 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use iris_crypto::PublicKey;
+use iris_ztd::{Digest, Hashable};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use alloc::{boxed::Box, format, string::ToString};
+
+use super::note::{Balance, Note};
+use super::v1::{NockchainTx, Spend0V1, Spend1V1, SpendV1 as Spend};
+use super::{Name, TxEngineSettings, Version};
+use crate::Nicks;
+
+/// A single consensus rule broken by a transaction.
+///
+/// Produced by [`verify_tx`]. Every violation names the input it was found in, where applicable.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi, from_wasm_abi))]
+pub enum TxViolation {
+    /// Transaction is not a V1 transaction.
+    InvalidVersion,
+    /// Transaction spends no notes.
+    NoSpends,
+    /// Spent note is not present in the balance.
+    InputNotFound { name: Name },
+    /// Spend kind does not match the note version (legacy spend of a V1 note or vice versa).
+    SpendVersionMismatch { name: Name },
+    /// Spend produces no outputs.
+    NoSeeds { name: Name },
+    /// Seed does not commit to the hash of the note being spent.
+    ParentHashMismatch {
+        name: Name,
+        expected: Digest,
+        got: Digest,
+    },
+    /// Gifts plus fee do not add up to the note's assets.
+    Unbalanced {
+        name: Name,
+        assets: Nicks,
+        spent: Nicks,
+    },
+    /// Lock merkle proof does not hash up to its own root.
+    InvalidLockProof { name: Name },
+    /// Lock merkle proof root does not match the lock root committed to by the note name.
+    LockRootMismatch { name: Name },
+    /// Spend condition contains a burn primitive, so it can never be unlocked.
+    Burned { name: Name },
+    /// Signature entry is keyed by a hash that is not the hash of its public key.
+    PkhMismatch { name: Name, pkh: Digest },
+    /// Signature does not verify against the spend's sig hash.
+    InvalidSignature { name: Name, pubkey: PublicKey },
+    /// Not enough valid signatures to satisfy an m-of-n condition.
+    InsufficientSignatures { name: Name, needed: u64, got: u64 },
+    /// Preimage in the hax map does not hash to its key.
+    InvalidPreimage { name: Name, hash: Digest },
+    /// Hax primitive requires a preimage that is not provided.
+    MissingPreimage { name: Name, hash: Digest },
+    /// Total fee is below what the transaction engine requires.
+    InsufficientFee { needed: Nicks, got: Nicks },
+    /// Spend fees add up to more than fits in a [`Nicks`] amount.
+    FeeOverflow,
+}
+
+impl core::fmt::Display for TxViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TxViolation::InvalidVersion => write!(f, "Transaction is not V1"),
+            TxViolation::NoSpends => write!(f, "Transaction has no spends"),
+            TxViolation::InputNotFound { name } => write!(f, "Input note {name} not found"),
+            TxViolation::SpendVersionMismatch { name } => {
+                write!(f, "Spend of {name} does not match the note version")
+            }
+            TxViolation::NoSeeds { name } => write!(f, "Spend of {name} has no seeds"),
+            TxViolation::ParentHashMismatch {
+                name,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Seed parent hash of {name} is {got}, expected note hash {expected}"
+            ),
+            TxViolation::Unbalanced {
+                name,
+                assets,
+                spent,
+            } => write!(
+                f,
+                "Spend of {name} is unbalanced (assets: {assets}, gifts + fee: {spent})"
+            ),
+            TxViolation::InvalidLockProof { name } => {
+                write!(f, "Lock merkle proof of {name} is invalid")
+            }
+            TxViolation::LockRootMismatch { name } => {
+                write!(f, "Lock merkle proof root does not match note {name}")
+            }
+            TxViolation::Burned { name } => {
+                write!(f, "Spend condition of {name} contains a burn")
+            }
+            TxViolation::PkhMismatch { name, pkh } => {
+                write!(
+                    f,
+                    "Signature of {name} under {pkh} has mismatched public key"
+                )
+            }
+            TxViolation::InvalidSignature { name, pubkey } => {
+                write!(f, "Invalid signature of {name} by {}", pubkey.to_hex())
+            }
+            TxViolation::InsufficientSignatures { name, needed, got } => {
+                write!(f, "Spend of {name} needs {needed} signatures, got {got}")
+            }
+            TxViolation::InvalidPreimage { name, hash } => {
+                write!(f, "Preimage for {hash} in {name} does not match")
+            }
+            TxViolation::MissingPreimage { name, hash } => {
+                write!(f, "Spend of {name} is missing preimage for {hash}")
+            }
+            TxViolation::InsufficientFee { needed, got } => write!(
+                f,
+                "Insufficient fee for transaction (needed: {needed}, got: {got})"
+            ),
+            TxViolation::FeeOverflow => write!(f, "Total fee of transaction overflows"),
+        }
+    }
+}
+
+/// Run the consensus checks a node performs on a transaction.
+///
+/// Inputs are looked up in `balance`, which must contain every note the transaction spends.
+/// Returns all violations found, rather than stopping at the first one.
+///
+/// Timelocks are not checked, as they depend on the height the transaction gets included at.
+pub fn verify_tx(
+    tx: &NockchainTx,
+    balance: &Balance,
+    settings: &TxEngineSettings,
+) -> Result<(), Vec<TxViolation>> {
+    if tx.version != Version::V1 {
+        return Err(vec![TxViolation::InvalidVersion]);
+    }
+
+    let raw = tx.to_raw_tx();
+    let mut violations = vec![];
+
+    if raw.spends.0.is_empty() {
+        violations.push(TxViolation::NoSpends);
+    }
+
+    for (name, spend) in &raw.spends.0 {
+        let Some(note) = balance.0.get(name) else {
+            violations.push(TxViolation::InputNotFound { name: *name });
+            continue;
+        };
+
+        verify_accounting(*name, note, spend, &mut violations);
+
+        match (spend, note) {
+            (Spend::S0(spend), Note::V0(note)) => {
+                verify_legacy(*name, spend, &note.sig, &mut violations)
+            }
+            (Spend::S1(spend), Note::V1(_)) => verify_witness(*name, spend, &mut violations),
+            _ => violations.push(TxViolation::SpendVersionMismatch { name: *name }),
+        }
+    }
+
+    // Sum in u128, as `total_fees` would overflow on hostile fees
+    let needed = raw.spends.fee(settings);
+    let got = raw
+        .spends
+        .0
+        .iter()
+        .fold(0u128, |acc, (_, s)| acc + s.fee().0 as u128);
+    match u64::try_from(got) {
+        Ok(got) if Nicks(got) < needed => {
+            violations.push(TxViolation::InsufficientFee {
+                needed,
+                got: Nicks(got),
+            });
+        }
+        Ok(_) => {}
+        Err(_) => violations.push(TxViolation::FeeOverflow),
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+fn verify_accounting(name: Name, note: &Note, spend: &Spend, violations: &mut Vec<TxViolation>) {
+    let seeds = &spend.seeds().0;
+    if seeds.is_empty() {
+        violations.push(TxViolation::NoSeeds { name });
+    }
+
+    let expected = note.hash();
+    for seed in seeds {
+        if seed.parent_hash != expected {
+            violations.push(TxViolation::ParentHashMismatch {
+                name,
+                expected,
+                got: seed.parent_hash,
+            });
+        }
+    }
+
+    // Sum in u128, so that hostile gifts cannot overflow the check
+    let spent = seeds
+        .iter()
+        .fold(spend.fee().0 as u128, |acc, s| acc + s.gift.0 as u128);
+    if spent != note.assets().0 as u128 {
+        violations.push(TxViolation::Unbalanced {
+            name,
+            assets: note.assets(),
+            spent: Nicks(spent.min(u64::MAX as u128) as u64),
+        });
+    }
+}
+
+fn verify_legacy(
+    name: Name,
+    spend: &Spend0V1,
+    sig: &super::v0::Sig,
+    violations: &mut Vec<TxViolation>,
+) {
+    let sig_hash = spend.sig_hash();
+    let mut valid = 0;
+
+    for (pubkey, signature) in spend.signature.0.iter() {
+        if !pubkey.verify(&sig_hash, signature) {
+            violations.push(TxViolation::InvalidSignature {
+                name,
+                pubkey: *pubkey,
+            });
+        } else if sig.pubkeys.contains(pubkey) {
+            valid += 1;
+        }
+    }
+
+    if valid < sig.m {
+        violations.push(TxViolation::InsufficientSignatures {
+            name,
+            needed: sig.m,
+            got: valid,
+        });
+    }
+}
+
+fn verify_witness(name: Name, spend: &Spend1V1, violations: &mut Vec<TxViolation>) {
+    let witness = &spend.witness;
+    let lmp = &witness.lock_merkle_proof;
+    let sc = lmp.spend_condition();
+
+    if !lmp.proof().verify(lmp.axis(), sc) {
+        violations.push(TxViolation::InvalidLockProof { name });
+    }
+
+    if (true, lmp.proof().root).hash() != name.first {
+        violations.push(TxViolation::LockRootMismatch { name });
+    }
+
+    if sc.brn() {
+        violations.push(TxViolation::Burned { name });
+    }
+
+    let sig_hash = spend.sig_hash();
+    let mut signed: Vec<Digest> = vec![];
+
+    for (pkh, (pubkey, signature)) in witness.pkh_signature.0.iter() {
+        if pubkey.hash() != *pkh {
+            violations.push(TxViolation::PkhMismatch { name, pkh: *pkh });
+        } else if !pubkey.verify(&sig_hash, signature) {
+            violations.push(TxViolation::InvalidSignature {
+                name,
+                pubkey: *pubkey,
+            });
+        } else {
+            signed.push(*pkh);
+        }
+    }
+
+    for p in sc.pkh() {
+        let got = p.hashes.iter().filter(|h| signed.contains(h)).count() as u64;
+        if got < p.m {
+            violations.push(TxViolation::InsufficientSignatures {
+                name,
+                needed: p.m,
+                got,
+            });
+        }
+    }
+
+    for (hash, preimage) in witness.hax_map.iter() {
+        if preimage.hash() != *hash {
+            violations.push(TxViolation::InvalidPreimage { name, hash: *hash });
+        }
+    }
+
+    for h in sc.hax() {
+        for hash in h.preimages.iter() {
+            if !witness.hax_map.contains(hash) {
+                violations.push(TxViolation::MissingPreimage { name, hash: *hash });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v1::{Lock, LockPrimitive, LockTim, NoteData, NoteV1, Pkh, SpendCondition};
+    use crate::TxBuilder;
+    use bip39::Mnemonic;
+    use iris_crypto::{derive_master_key, PrivateKey};
+    use iris_ztd::ZMap;
+
+    fn keys() -> PrivateKey {
+        let mnemonic = Mnemonic::parse("dice domain inspire horse time initial monitor nature mass impose tone benefit vibrant dash kiss mosquito rice then color ribbon agent method drop fat").unwrap();
+        derive_master_key(&mnemonic.to_seed(""))
+            .private_key
+            .unwrap()
+    }
+
+    fn signed_tx() -> (NockchainTx, Balance, TxEngineSettings) {
+        let private_key = keys();
+        let note = Note::V1(NoteV1 {
+            version: Version::V1,
+            origin_page: 13,
+            name: Name::new(
+                "2H7WHTE9dFXiGgx4J432DsCLuMovNkokfcnCGRg7utWGM9h13PgQvsH"
+                    .try_into()
+                    .unwrap(),
+                "7yMzrJjkb2Xu8uURP7YB3DFcotttR8dKDXF1tSp2wJmmXUvLM7SYzvM"
+                    .try_into()
+                    .unwrap(),
+            ),
+            note_data: NoteData::empty(),
+            assets: Nicks(4294967296),
+        });
+        let spend_condition: (Lock, usize) = (
+            SpendCondition(
+                [
+                    LockPrimitive::Pkh(Pkh::single(private_key.public_key().hash())),
+                    LockPrimitive::Tim(LockTim::coinbase()),
+                ]
+                .into(),
+            )
+            .into(),
+            0,
+        );
+        let settings = TxEngineSettings::v1_default();
+        let tx = TxBuilder::new(settings)
+            .simple_spend(
+                vec![(note.clone(), Some(spend_condition))],
+                "2nEFkqYm51yfqsYgfRx72w8FF9bmWqnkJu8XqY8T7psXufjYNRxf5ME"
+                    .try_into()
+                    .unwrap(),
+                Nicks(1234567),
+                private_key.public_key().hash(),
+                true,
+            )
+            .unwrap()
+            .sign(&private_key)
+            .build();
+        let balance = Balance(ZMap::from([(note.name(), note)]));
+        (tx, balance, settings)
+    }
+
+    #[test]
+    fn test_verify_valid() {
+        let (tx, balance, settings) = signed_tx();
+        assert_eq!(verify_tx(&tx, &balance, &settings), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_missing_input() {
+        let (tx, _, settings) = signed_tx();
+        let name = tx.spends.0.iter().next().map(|(n, _)| *n).unwrap();
+        assert_eq!(
+            verify_tx(&tx, &Balance(ZMap::new()), &settings),
+            Err(vec![TxViolation::InputNotFound { name }])
+        );
+    }
+
+    #[test]
+    fn test_verify_bad_signature() {
+        let (mut tx, balance, settings) = signed_tx();
+        let name = tx.spends.0.iter().next().map(|(n, _)| *n).unwrap();
+        let witness = tx.witness_data.data.get_mut(&name).unwrap();
+        let pkh = *witness.pkh_signature.0.iter().next().unwrap().0;
+        let (pubkey, signature) = witness.pkh_signature.0.get_mut(&pkh).unwrap();
+        let pubkey = *pubkey;
+        signature.s = signature.s.wrapping_add(&iris_ztd::U256::ONE);
+
+        assert_eq!(
+            verify_tx(&tx, &balance, &settings),
+            Err(vec![
+                TxViolation::InvalidSignature { name, pubkey },
+                TxViolation::InsufficientSignatures {
+                    name,
+                    needed: 1,
+                    got: 0
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_verify_fee_and_balance() {
+        let (tx, balance, _) = signed_tx();
+        let name = tx.spends.0.iter().next().map(|(n, _)| *n).unwrap();
+        let expensive = TxEngineSettings::v1_with_word_cost(Nicks(1 << 17));
+        let Err(violations) = verify_tx(&tx, &balance, &expensive) else {
+            panic!("Expected insufficient fee");
+        };
+        assert!(matches!(
+            violations[..],
+            [TxViolation::InsufficientFee { .. }]
+        ));
+
+        let mut balance = balance;
+        let Some(Note::V1(note)) = balance.0.get_mut(&name) else {
+            panic!("Expected V1 note");
+        };
+        note.assets += 1;
+        let Err(violations) = verify_tx(&tx, &balance, &TxEngineSettings::v1_default()) else {
+            panic!("Expected violations");
+        };
+        // Changing the note also changes its hash, which the seeds commit to
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, TxViolation::Unbalanced { .. })));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, TxViolation::ParentHashMismatch { .. })));
+    }
+
+    #[test]
+    fn test_verify_fee_overflow() {
+        let (mut tx, balance, settings) = signed_tx();
+        let name = tx.spends.0.iter().next().map(|(n, _)| *n).unwrap();
+        let Some(Spend::S1(spend)) = tx.spends.0.get_mut(&name) else {
+            panic!("Expected witness spend");
+        };
+        spend.fee = Nicks(1 << 63);
+        let spend = spend.clone();
+        let other = Name::new(name.first, 7u64.hash());
+        tx.spends.0.insert(other, Spend::S1(spend));
+
+        let Err(violations) = verify_tx(&tx, &balance, &settings) else {
+            panic!("Expected violations");
+        };
+        assert!(violations.contains(&TxViolation::FeeOverflow));
+        assert!(violations.contains(&TxViolation::InputNotFound { name: other }));
+    }
+}
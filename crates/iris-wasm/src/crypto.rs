@@ -1,12 +1,24 @@
+use iris_ztd::crypto::cheetah::G_ORDER;
 use iris_ztd::U256;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 use iris_crypto::cheetah::{PrivateKey, PublicKey, Signature};
-use iris_crypto::slip10::{derive_master_key as derive_master_key_internal, ExtendedKey};
+use iris_crypto::slip10::{
+    derive_master_key as derive_master_key_internal, DerivationPath, DerivationPathError,
+    ExtendedKey,
+};
+use iris_crypto::{
+    gen_master_key as gen_master_key_internal, recover_mnemonic as recover_mnemonic_internal,
+    split_mnemonic as split_mnemonic_internal, KeygenParams, Keystore, KeystoreMetadata,
+    KeystoreSecret, SignedMessage,
+};
+use iris_ztd::{Digest, Hashable};
+use rand_core::OsRng;
+use zeroize::{Zeroize, Zeroizing};
 
 #[wasm_bindgen(js_name = ExtendedKey)]
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WasmExtendedKey {
     #[wasm_bindgen(skip)]
     pub private_key: Option<Vec<u8>>,
@@ -14,6 +26,22 @@ pub struct WasmExtendedKey {
     pub public_key: Vec<u8>,
     #[wasm_bindgen(skip)]
     pub chain_code: Vec<u8>,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub depth: u8,
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub parent_fingerprint: [u8; 4],
+    #[wasm_bindgen(skip)]
+    #[serde(default)]
+    pub child_index: u32,
+}
+
+impl Drop for WasmExtendedKey {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+        self.chain_code.zeroize();
+    }
 }
 
 #[wasm_bindgen(js_class = ExtendedKey)]
@@ -33,16 +61,80 @@ impl WasmExtendedKey {
         self.chain_code.clone()
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    #[wasm_bindgen(getter, js_name = parentFingerprint)]
+    pub fn parent_fingerprint(&self) -> Vec<u8> {
+        self.parent_fingerprint.to_vec()
+    }
+
+    #[wasm_bindgen(getter, js_name = childIndex)]
+    pub fn child_index(&self) -> u32 {
+        self.child_index
+    }
+
+    /// Serialize as a base58check extended key (xprv if the private key is present, else xpub)
+    #[wasm_bindgen(js_name = toBase58)]
+    pub fn to_base58(&self) -> Result<String, JsValue> {
+        let extended_key = self.to_internal().map_err(|e| JsValue::from_str(&e))?;
+        Ok(extended_key.to_string())
+    }
+
+    /// Serialize the extended public key only
+    #[wasm_bindgen(js_name = toXpub)]
+    pub fn to_xpub(&self) -> Result<String, JsValue> {
+        let extended_key = self.to_internal().map_err(|e| JsValue::from_str(&e))?;
+        let xpub = extended_key.xpub().to_string();
+        Ok(xpub)
+    }
+
+    /// Parse a base58check extended private or public key
+    #[wasm_bindgen(js_name = fromBase58)]
+    pub fn from_base58(s: &str) -> Result<WasmExtendedKey, JsValue> {
+        let key = ExtendedKey::from_base58(s).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(WasmExtendedKey::from_internal(&key))
+    }
+
     /// Derive a child key at the given index
     #[wasm_bindgen(js_name = deriveChild)]
     pub fn derive_child(&self, index: u32) -> Result<WasmExtendedKey, JsValue> {
         let extended_key = self.to_internal().map_err(|e| JsValue::from_str(&e))?;
 
-        let child = extended_key.derive_child(index);
+        let child = extended_key
+            .try_derive_child(index)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(WasmExtendedKey::from_internal(&child))
+    }
+
+    /// Derive the descendant at a path such as `m/44'/0'/0/5`, relative to this key
+    #[wasm_bindgen(js_name = derivePath)]
+    pub fn derive_path(&self, path: &str) -> Result<WasmExtendedKey, JsValue> {
+        let extended_key = self.to_internal().map_err(|e| JsValue::from_str(&e))?;
+        let path: DerivationPath = path
+            .parse()
+            .map_err(|e: DerivationPathError| JsValue::from_str(&e.to_string()))?;
+        let child = extended_key
+            .derive_path(&path)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
         Ok(WasmExtendedKey::from_internal(&child))
     }
 
-    fn to_internal(&self) -> Result<ExtendedKey, String> {
+    /// Drop the private key, keeping a watch-only copy
+    pub fn neuter(&self) -> WasmExtendedKey {
+        WasmExtendedKey {
+            private_key: None,
+            public_key: self.public_key.clone(),
+            chain_code: self.chain_code.clone(),
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_index: self.child_index,
+        }
+    }
+
+    pub(crate) fn to_internal(&self) -> Result<ExtendedKey, String> {
         let private_key = if let Some(pk_bytes) = &self.private_key {
             if pk_bytes.len() != 32 {
                 return Err("Private key must be 32 bytes".to_string());
@@ -62,21 +154,30 @@ impl WasmExtendedKey {
         if self.chain_code.len() != 32 {
             return Err("Chain code must be 32 bytes".to_string());
         }
-        let mut chain_code = [0u8; 32];
+        let mut chain_code = Zeroizing::new([0u8; 32]);
         chain_code.copy_from_slice(&self.chain_code);
 
         Ok(ExtendedKey {
             private_key,
             public_key,
             chain_code,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_index: self.child_index,
         })
     }
 
     fn from_internal(key: &ExtendedKey) -> Self {
         WasmExtendedKey {
-            private_key: key.private_key.as_ref().map(|pk| pk.to_be_bytes().to_vec()),
+            private_key: key
+                .private_key
+                .as_ref()
+                .map(|pk| Zeroizing::new(pk.to_be_bytes()).to_vec()),
             public_key: key.public_key.to_be_bytes().to_vec(),
             chain_code: key.chain_code.to_vec(),
+            depth: key.depth,
+            parent_fingerprint: key.parent_fingerprint,
+            child_index: key.child_index,
         }
     }
 }
@@ -88,6 +189,193 @@ pub fn derive_master_key(seed: &[u8]) -> WasmExtendedKey {
     WasmExtendedKey::from_internal(&key)
 }
 
+#[wasm_bindgen(js_name = GeneratedKey)]
+pub struct WasmGeneratedKey {
+    mnemonic: Zeroizing<String>,
+    params: KeygenParams,
+    master_key: WasmExtendedKey,
+}
+
+#[wasm_bindgen(js_class = GeneratedKey)]
+impl WasmGeneratedKey {
+    #[wasm_bindgen(getter)]
+    pub fn mnemonic(&self) -> String {
+        self.mnemonic.to_string()
+    }
+
+    /// Parameters the mnemonic was generated with; store them to reproduce the keygen.
+    #[wasm_bindgen(getter)]
+    pub fn params(&self) -> KeygenParams {
+        self.params
+    }
+
+    #[wasm_bindgen(getter, js_name = masterKey)]
+    pub fn master_key(&self) -> WasmExtendedKey {
+        self.master_key.clone()
+    }
+}
+
+/// Parameters used by `nockchain-wallet keygen`: Argon2d, 768 MiB, 6 iterations, 4 lanes.
+#[wasm_bindgen(js_name = nockchainCliKeygenParams)]
+pub fn nockchain_cli_keygen_params() -> KeygenParams {
+    KeygenParams::NOCKCHAIN_CLI
+}
+
+/// Argon2id with 64 MiB and a single lane, for browsers and low-memory devices.
+#[wasm_bindgen(js_name = lightKeygenParams)]
+pub fn light_keygen_params() -> KeygenParams {
+    KeygenParams::LIGHT
+}
+
+/// Generate a master key from entropy and salt using Argon2 + BIP39 + SLIP-10
+///
+/// `params` is required: the Nockchain CLI parameters (768 MiB) may not fit in browser memory,
+/// and the light preset does not reproduce CLI keys. Store the parameters with the mnemonic.
+#[wasm_bindgen(js_name = genMasterKey)]
+pub fn gen_master_key(
+    entropy: &[u8],
+    salt: &[u8],
+    params: KeygenParams,
+) -> Result<WasmGeneratedKey, JsValue> {
+    let generated = gen_master_key_internal(entropy, salt, &params)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(WasmGeneratedKey {
+        mnemonic: generated.mnemonic,
+        params: generated.params,
+        master_key: WasmExtendedKey::from_internal(&generated.master_key),
+    })
+}
+
+fn encrypt_keystore(
+    secret: &KeystoreSecret,
+    passphrase: &str,
+    params: Option<KeygenParams>,
+    metadata: KeystoreMetadata,
+) -> Result<String, JsValue> {
+    let keystore = Keystore::encrypt(
+        secret,
+        passphrase.as_bytes(),
+        &params.unwrap_or(KeygenParams::LIGHT),
+        metadata,
+        &mut OsRng,
+    )
+    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(keystore.to_json())
+}
+
+/// Encrypt a 32-byte private key into a JSON keystore.
+///
+/// `params` defaults to the light preset and must cost at least 19 MiB and 2 iterations; the
+/// key's PKH is recorded in the metadata.
+#[wasm_bindgen(js_name = encryptPrivateKeyKeystore)]
+pub fn encrypt_private_key_keystore(
+    private_key: &[u8],
+    passphrase: &str,
+    params: Option<KeygenParams>,
+    derivation_path: Option<String>,
+) -> Result<String, JsValue> {
+    if private_key.len() != 32 {
+        return Err(JsValue::from_str("Private key must be 32 bytes"));
+    }
+    let s = U256::from_be_slice(private_key);
+    if s == U256::ZERO || s >= G_ORDER {
+        return Err(JsValue::from_str("Invalid private key"));
+    }
+    let key = PrivateKey(s);
+    let metadata = KeystoreMetadata {
+        derivation_path,
+        pkh: Some(key.public_key().hash()),
+    };
+    encrypt_keystore(
+        &KeystoreSecret::PrivateKey(key),
+        passphrase,
+        params,
+        metadata,
+    )
+}
+
+/// Encrypt a mnemonic into a JSON keystore. `params` defaults to the light preset and must cost
+/// at least 19 MiB and 2 iterations.
+#[wasm_bindgen(js_name = encryptMnemonicKeystore)]
+pub fn encrypt_mnemonic_keystore(
+    mnemonic: &str,
+    passphrase: &str,
+    params: Option<KeygenParams>,
+) -> Result<String, JsValue> {
+    encrypt_keystore(
+        &KeystoreSecret::mnemonic(mnemonic),
+        passphrase,
+        params,
+        KeystoreMetadata::default(),
+    )
+}
+
+#[wasm_bindgen(js_name = DecryptedKeystore)]
+pub struct WasmDecryptedKeystore {
+    secret: KeystoreSecret,
+    metadata: KeystoreMetadata,
+}
+
+#[wasm_bindgen(js_class = DecryptedKeystore)]
+impl WasmDecryptedKeystore {
+    #[wasm_bindgen(getter, js_name = privateKey)]
+    pub fn private_key(&self) -> Option<Vec<u8>> {
+        match &self.secret {
+            KeystoreSecret::PrivateKey(key) => Some(key.to_be_bytes().to_vec()),
+            KeystoreSecret::Mnemonic(_) => None,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mnemonic(&self) -> Option<String> {
+        match &self.secret {
+            KeystoreSecret::PrivateKey(_) => None,
+            KeystoreSecret::Mnemonic(mnemonic) => Some(mnemonic.to_string()),
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = derivationPath)]
+    pub fn derivation_path(&self) -> Option<String> {
+        self.metadata.derivation_path.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pkh(&self) -> Option<Digest> {
+        self.metadata.pkh
+    }
+}
+
+/// Decrypt a JSON keystore produced by `encryptPrivateKeyKeystore`/`encryptMnemonicKeystore`
+/// or by a native wallet.
+#[wasm_bindgen(js_name = decryptKeystore)]
+pub fn decrypt_keystore(json: &str, passphrase: &str) -> Result<WasmDecryptedKeystore, JsValue> {
+    let keystore = Keystore::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let secret = keystore
+        .decrypt(passphrase.as_bytes())
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(WasmDecryptedKeystore {
+        secret,
+        metadata: keystore.metadata,
+    })
+}
+
+/// Split a mnemonic into `count` base58 shares, any `threshold` of which recover it.
+#[wasm_bindgen(js_name = splitMnemonic)]
+pub fn split_mnemonic(mnemonic: &str, threshold: u8, count: u8) -> Result<Vec<String>, JsValue> {
+    let mnemonic = bip39::Mnemonic::parse(mnemonic)
+        .map_err(|e| JsValue::from_str(&format!("Invalid mnemonic: {}", e)))?;
+    split_mnemonic_internal(&mnemonic, threshold, count, &mut OsRng)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Recover a mnemonic from shares produced by `splitMnemonic`.
+#[wasm_bindgen(js_name = recoverMnemonic)]
+pub fn recover_mnemonic(shares: Vec<String>) -> Result<String, JsValue> {
+    recover_mnemonic_internal(&shares)
+        .map(|mnemonic| mnemonic.to_string())
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 /// Derive master key from BIP39 mnemonic phrase
 #[wasm_bindgen(js_name = deriveMasterKeyFromMnemonic)]
 pub fn derive_master_key_from_mnemonic(
@@ -138,6 +426,8 @@ pub fn hash_noun(noun: &[u8]) -> Result<String, JsValue> {
 }
 
 /// Sign a message string with a private key
+///
+/// The message is hashed without domain separation; prefer `createSignedMessage` for new uses.
 #[wasm_bindgen(js_name = signMessage)]
 pub fn sign_message(private_key_bytes: &[u8], message: &str) -> Result<Signature, JsValue> {
     use iris_ztd::{Belt, Hashable, NounEncode};
@@ -166,3 +456,57 @@ pub fn verify_signature(
     let digest = Belt::from_bytes(message.as_bytes()).to_noun().hash();
     Ok(public_key.verify(&digest, signature))
 }
+
+#[wasm_bindgen(js_name = SignedMessage)]
+pub struct WasmSignedMessage {
+    inner: SignedMessage,
+}
+
+#[wasm_bindgen(js_class = SignedMessage)]
+impl WasmSignedMessage {
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.inner.public_key.to_be_bytes().to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pkh(&self) -> Digest {
+        self.inner.public_key.hash()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        String::from_utf8_lossy(&self.inner.message).into_owned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn signature(&self) -> Signature {
+        self.inner.signature
+    }
+
+    #[wasm_bindgen(js_name = toBase58)]
+    pub fn to_base58(&self) -> String {
+        self.inner.to_base58()
+    }
+}
+
+/// Sign a message with domain separation and return the portable base58 signed message.
+#[wasm_bindgen(js_name = createSignedMessage)]
+pub fn create_signed_message(private_key_bytes: &[u8], message: &str) -> Result<String, JsValue> {
+    if private_key_bytes.len() != 32 {
+        return Err(JsValue::from_str("Private key must be 32 bytes"));
+    }
+    let private_key = PrivateKey(U256::from_be_slice(private_key_bytes));
+    Ok(private_key.sign_message(message.as_bytes()).to_base58())
+}
+
+/// Parse and verify a base58 signed message, failing if the signature is invalid.
+#[wasm_bindgen(js_name = verifySignedMessage)]
+pub fn verify_signed_message(signed_message: &str) -> Result<WasmSignedMessage, JsValue> {
+    let inner = SignedMessage::from_base58(signed_message)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    if !inner.verify() {
+        return Err(JsValue::from_str("Invalid signature"));
+    }
+    Ok(WasmSignedMessage { inner })
+}
@@ -13,6 +13,18 @@ pub fn jam(noun: Noun) -> Result<Vec<u8>, JsValue> {
     Ok(jam_internal(noun))
 }
 
+/// Print a Noun in Hoon notation, e.g. `[%foo 'bar' 1.000]`.
+#[wasm_bindgen(js_name = "nounToHoon")]
+pub fn noun_to_hoon(noun: Noun) -> String {
+    iris_ztd::text::print(&noun)
+}
+
+/// Parse a Noun from Hoon notation (see `nounToHoon`).
+#[wasm_bindgen(js_name = "hoonToNoun")]
+pub fn hoon_to_noun(s: &str) -> Result<Noun, JsValue> {
+    iris_ztd::text::parse(s).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 /// Convert string to an Atom.
 #[wasm_bindgen]
 pub fn tas(s: &str) -> Noun {
@@ -2,7 +2,7 @@ use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
-use iris_crypto::PrivateKey as CryptoPrivateKey;
+use iris_crypto::{DerivationPath, DerivationPathError, PrivateKey as CryptoPrivateKey};
 use iris_grpc_proto::pb::common::v1 as pb_v1;
 use iris_grpc_proto::pb::common::v2 as pb;
 use iris_nockchain_types::{
@@ -16,6 +16,8 @@ use iris_ztd::{cue, Digest, U256};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+use crate::crypto::WasmExtendedKey;
+
 // ============================================================================
 // Wasm Types - Adapters and Helpers
 // ============================================================================
@@ -127,6 +129,10 @@ impl TxLock {
 
 enum PrivateKeyBackend {
     Bytes(BytesPrivateKeyBackend),
+    Derived {
+        key: BytesPrivateKeyBackend,
+        path: String,
+    },
 }
 
 struct BytesPrivateKeyBackend {
@@ -175,22 +181,40 @@ impl WasmPrivateKey {
         }
 
         let signing_key = CryptoPrivateKey(U256::from_be_slice(signing_key_bytes));
-        let public_key_bytes = signing_key.public_key().to_be_bytes();
+        Ok(Self {
+            backend: PrivateKeyBackend::Bytes(BytesPrivateKeyBackend::new(signing_key)),
+        })
+    }
 
+    /// Derive a key at `path` (e.g. `m/44'/0'/0/5`) from an extended private key.
+    ///
+    /// The path is relative to `root` and is reported back by `derivationPath`.
+    #[wasm_bindgen(js_name = fromExtendedKey)]
+    pub fn from_extended_key(root: &WasmExtendedKey, path: &str) -> Result<Self, JsValue> {
+        let path: DerivationPath = path
+            .parse()
+            .map_err(|e: DerivationPathError| JsValue::from_str(&e.to_string()))?;
+        let mut derived = root
+            .to_internal()
+            .map_err(|e| JsValue::from_str(&e))?
+            .derive_path(&path)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let signing_key = derived
+            .private_key
+            .take()
+            .ok_or_else(|| JsValue::from_str("Extended key has no private key"))?;
         Ok(Self {
-            backend: PrivateKeyBackend::Bytes(BytesPrivateKeyBackend {
-                signing_key,
-                public_key_bytes,
-            }),
+            backend: PrivateKeyBackend::Derived {
+                key: BytesPrivateKeyBackend::new(signing_key),
+                path: path.to_string(),
+            },
         })
     }
 
     /// Return this key's public key as 97-byte uncompressed bytes.
     #[wasm_bindgen(getter, js_name = publicKey)]
     pub fn public_key(&self) -> Vec<u8> {
-        match &self.backend {
-            PrivateKeyBackend::Bytes(bytes_backend) => bytes_backend.public_key_bytes.to_vec(),
-        }
+        self.bytes_backend().public_key_bytes.to_vec()
     }
 
     /// Return the derivation path for this key backend, if available.
@@ -200,6 +224,7 @@ impl WasmPrivateKey {
     pub fn derivation_path(&self) -> Option<String> {
         match &self.backend {
             PrivateKeyBackend::Bytes(_) => None,
+            PrivateKeyBackend::Derived { path, .. } => Some(path.clone()),
         }
     }
 
@@ -208,16 +233,32 @@ impl WasmPrivateKey {
     pub fn backend_kind(&self) -> String {
         match &self.backend {
             PrivateKeyBackend::Bytes(_) => "bytes".to_string(),
+            PrivateKeyBackend::Derived { .. } => "derived".to_string(),
+        }
+    }
+}
+
+impl BytesPrivateKeyBackend {
+    fn new(signing_key: CryptoPrivateKey) -> Self {
+        let public_key_bytes = signing_key.public_key().to_be_bytes();
+        Self {
+            signing_key,
+            public_key_bytes,
         }
     }
 }
 
 impl WasmPrivateKey {
-    fn signing_key(&self) -> &CryptoPrivateKey {
+    fn bytes_backend(&self) -> &BytesPrivateKeyBackend {
         match &self.backend {
-            PrivateKeyBackend::Bytes(bytes_backend) => &bytes_backend.signing_key,
+            PrivateKeyBackend::Bytes(bytes_backend) => bytes_backend,
+            PrivateKeyBackend::Derived { key, .. } => key,
         }
     }
+
+    fn signing_key(&self) -> &CryptoPrivateKey {
+        &self.bytes_backend().signing_key
+    }
 }
 
 #[wasm_bindgen(js_name = TxBuilder)]
@@ -357,6 +398,19 @@ impl WasmTxBuilder {
         Ok(self.builder.build())
     }
 
+    /// Export this builder as a jammed partially signed transaction, for co-signers.
+    #[wasm_bindgen(js_name = exportPstx)]
+    pub fn export_pstx(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.builder.export_pstx().map_err(|e| e.to_string())?)
+    }
+
+    /// Reconstruct a builder from a jammed partially signed transaction.
+    #[wasm_bindgen(js_name = importPstx)]
+    pub fn import_pstx(pstx: &[u8]) -> Result<Self, JsValue> {
+        let builder = TxBuilder::import_pstx(pstx).map_err(|e| e.to_string())?;
+        Ok(Self { builder })
+    }
+
     #[wasm_bindgen(js_name = allSpends)]
     pub fn all_spends(&self) -> Vec<WasmSpendBuilder> {
         self.builder
@@ -367,6 +421,12 @@ impl WasmTxBuilder {
     }
 }
 
+/// Merge the signatures of two jammed partially signed transactions for the same transaction.
+#[wasm_bindgen(js_name = mergePstx)]
+pub fn merge_pstx(a: &[u8], b: &[u8]) -> Result<Vec<u8>, JsValue> {
+    Ok(iris_nockchain_types::merge_pstx([a, b]).map_err(|e| e.to_string())?)
+}
+
 // ============================================================================
 // Wasm Spend Builder
 // ============================================================================
@@ -833,6 +833,13 @@ pub fn wasm_member_methods(_attr: TokenStream, item: TokenStream) -> TokenStream
                 .filter(|a| a.path().is_ident("doc"))
                 .collect();
 
+            // Wrappers of deprecated methods call them on purpose
+            let allow_deprecated = method
+                .attrs
+                .iter()
+                .any(|a| a.path().is_ident("deprecated"))
+                .then(|| quote! { #[allow(deprecated)] });
+
             let mut args = Vec::new();
             let mut pass_args = Vec::new();
             let mut has_receiver = false;
@@ -894,6 +901,7 @@ pub fn wasm_member_methods(_attr: TokenStream, item: TokenStream) -> TokenStream
             generated_methods.push(quote! {
                 #(#doc_comments)*
                 #js_name_attr
+                #allow_deprecated
                 pub fn #method_ident(#(#args),*) #final_output {
                     #body
                 }
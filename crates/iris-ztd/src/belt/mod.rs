@@ -313,11 +313,7 @@ pub fn badd(a: u64, b: u64) -> u64 {
 #[inline(always)]
 pub fn bneg(a: u64) -> u64 {
     based!(a);
-    if a != 0 {
-        PRIME - a
-    } else {
-        0
-    }
+    bsub(0, a)
 }
 
 #[inline(always)]
@@ -337,23 +333,20 @@ pub fn reduce(n: u128) -> u64 {
 
 #[inline(always)]
 pub fn reduce_159(low: u64, mid: u32, high: u64) -> u64 {
-    let (mut low2, carry) = low.overflowing_sub(high);
-    if carry {
-        low2 = low2.wrapping_add(PRIME);
-    }
+    // Branch-free, as secret scalars go through here in constant-time point multiplication.
+    // Adding or subtracting 2^32 - 1 wraps to subtracting or adding PRIME.
+    let (low2, carry) = low.overflowing_sub(high);
+    let low2 = low2.wrapping_sub(0u32.wrapping_sub(carry as u32) as u64);
 
     let mut product = (mid as u64) << 32;
     product -= product >> 32;
 
-    let (mut result, carry) = product.overflowing_add(low2);
-    if carry {
-        result = result.wrapping_sub(PRIME);
-    }
+    let (result, carry) = product.overflowing_add(low2);
+    let result = result.wrapping_add(0u32.wrapping_sub(carry as u32) as u64);
 
-    if result >= PRIME {
-        result -= PRIME;
-    }
-    result
+    let (reduced, borrow) = result.overflowing_sub(PRIME);
+    let keep = 0u64.wrapping_sub(borrow as u64);
+    (result & keep) | (reduced & !keep)
 }
 
 #[inline(always)]
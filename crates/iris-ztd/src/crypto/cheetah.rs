@@ -1,6 +1,9 @@
 #[cfg(feature = "wasm")]
 use alloc::{boxed::Box, format, string::ToString};
+use core::cell::UnsafeCell;
 use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
 
 use bs58;
 use crypto_bigint::{MulMod, U256};
@@ -420,6 +423,9 @@ pub fn ch_add(p: &CheetahPoint, q: &CheetahPoint) -> Result<CheetahPoint, Cheeta
     ch_add_unsafe(*p, *q)
 }
 
+/// Scalar multiplication by a small scalar.
+///
+/// Runs in variable time; use [`ch_scal_ct`] for secret scalars.
 #[inline(always)]
 pub fn ch_scal(n: u64, p: &CheetahPoint) -> Result<CheetahPoint, CheetahError> {
     let mut n = n;
@@ -435,6 +441,10 @@ pub fn ch_scal(n: u64, p: &CheetahPoint) -> Result<CheetahPoint, CheetahError> {
     Ok(acc)
 }
 
+/// Scalar multiplication by double-and-add.
+///
+/// Branches on the bits of `n`, so it must only be used with public scalars (e.g. when
+/// verifying). Use [`ch_scal_ct`] for private keys and nonces.
 #[inline(always)]
 pub fn ch_scal_big(n: &U256, p: &CheetahPoint) -> Result<CheetahPoint, CheetahError> {
     if *n == U256::ZERO {
@@ -452,6 +462,241 @@ pub fn ch_scal_big(n: &U256, p: &CheetahPoint) -> Result<CheetahPoint, CheetahEr
     Ok(acc)
 }
 
+/// Multi-scalar multiplication: `Σ nᵢ·Pᵢ`.
+///
+/// Interleaves the terms over a single doubling chain (Straus' method), which is considerably
+/// cheaper than summing separate [`ch_scal_big`] results. Runs in variable time, so it must only
+/// be used with public scalars.
+pub fn ch_multi_scal(terms: &[(U256, CheetahPoint)]) -> Result<CheetahPoint, CheetahError> {
+    let mut acc = A_ID;
+    for i in (0..256).rev() {
+        acc = ch_double(acc)?;
+        for (n, p) in terms {
+            if n.bit_vartime(i) {
+                acc = ch_add(&acc, p)?;
+            }
+        }
+    }
+    Ok(acc)
+}
+
+/// Curve constant `3·b`, where the curve is `y² = x³ + x + b` with `b = 395 + X`.
+const B3: F6lt = F6lt([Belt(1185), Belt(3), Belt(0), Belt(0), Belt(0), Belt(0)]);
+
+/// `p⁶ - 2` as little-endian limbs, the exponent for inverting by Fermat's little theorem.
+const F6_INV_EXP: [u64; 6] = [
+    18446744047939747839,
+    18446743858961186836,
+    18446743532543672409,
+    18446743532543672460,
+    18446743858961186905,
+    18446744047939747860,
+];
+
+/// A point in homogeneous projective coordinates `(X : Y : Z)`, with the identity at `(0 : 1 : 0)`.
+#[derive(Clone, Copy)]
+struct ProjectivePoint {
+    x: F6lt,
+    y: F6lt,
+    z: F6lt,
+}
+
+impl ProjectivePoint {
+    const IDENTITY: Self = Self {
+        x: F6_ZERO,
+        y: F6_ONE,
+        z: F6_ZERO,
+    };
+
+    fn from_affine(p: &CheetahPoint) -> Self {
+        if p.inf {
+            return Self::IDENTITY;
+        }
+        Self {
+            x: p.x,
+            y: p.y,
+            z: F6_ONE,
+        }
+    }
+
+    /// Complete addition (Renes–Costello–Batina, algorithm 1 with `a = 1`).
+    ///
+    /// Handles doubling and the identity without branching, for any two points of the
+    /// odd-order subgroup.
+    fn add(&self, q: &Self) -> Self {
+        let (x1, y1, z1) = (&self.x, &self.y, &self.z);
+        let (x2, y2, z2) = (&q.x, &q.y, &q.z);
+
+        let t0 = f6_mul(x1, x2);
+        let t1 = f6_mul(y1, y2);
+        let t2 = f6_mul(z1, z2);
+        let t3 = f6_sub(&f6_mul(&f6_add(x1, y1), &f6_add(x2, y2)), &f6_add(&t0, &t1));
+        let t4 = f6_sub(&f6_mul(&f6_add(x1, z1), &f6_add(x2, z2)), &f6_add(&t0, &t2));
+        let t5 = f6_sub(&f6_mul(&f6_add(y1, z1), &f6_add(y2, z2)), &f6_add(&t1, &t2));
+
+        let z3 = f6_add(&f6_mul(&B3, &t2), &t4);
+        let x3 = f6_sub(&t1, &z3);
+        let z3 = f6_add(&t1, &z3);
+        let y3 = f6_mul(&x3, &z3);
+
+        let t1 = f6_add(&f6_scal(Belt(3), &t0), &t2);
+        let t4 = f6_add(&f6_mul(&B3, &t4), &f6_sub(&t0, &t2));
+
+        Self {
+            x: f6_sub(&f6_mul(&t3, &x3), &f6_mul(&t5, &t4)),
+            y: f6_add(&y3, &f6_mul(&t1, &t4)),
+            z: f6_add(&f6_mul(&t5, &z3), &f6_mul(&t3, &t1)),
+        }
+    }
+
+    fn to_affine(self) -> CheetahPoint {
+        if self.z == F6_ZERO {
+            return A_ID;
+        }
+        let z_inv = f6_inv_ct(&self.z);
+        CheetahPoint {
+            x: f6_mul(&self.x, &z_inv),
+            y: f6_mul(&self.y, &z_inv),
+            inf: false,
+        }
+    }
+}
+
+/// Swap `a` and `b` if `swap` is 1, without branching on it.
+#[inline(always)]
+fn f6_cswap(a: &mut F6lt, b: &mut F6lt, swap: u64) {
+    let mask = 0u64.wrapping_sub(swap);
+    for (a, b) in a.0.iter_mut().zip(b.0.iter_mut()) {
+        let t = mask & (a.0 ^ b.0);
+        a.0 ^= t;
+        b.0 ^= t;
+    }
+}
+
+#[inline(always)]
+fn ch_cmov(p: &mut ProjectivePoint, q: &ProjectivePoint, choose: u64) {
+    let (mut x, mut y, mut z) = (q.x, q.y, q.z);
+    f6_cswap(&mut p.x, &mut x, choose);
+    f6_cswap(&mut p.y, &mut y, choose);
+    f6_cswap(&mut p.z, &mut z, choose);
+}
+
+#[inline(always)]
+fn ch_cswap(p: &mut ProjectivePoint, q: &mut ProjectivePoint, swap: u64) {
+    f6_cswap(&mut p.x, &mut q.x, swap);
+    f6_cswap(&mut p.y, &mut q.y, swap);
+    f6_cswap(&mut p.z, &mut q.z, swap);
+}
+
+/// Inversion as `f^(p⁶-2)`; unlike [`f6_inv`], the sequence of operations does not depend on `f`.
+fn f6_inv_ct(f: &F6lt) -> F6lt {
+    let mut acc = F6_ONE;
+    for limb in F6_INV_EXP.iter().rev() {
+        for bit in (0..64).rev() {
+            acc = f6_square(&acc);
+            if (limb >> bit) & 1 == 1 {
+                acc = f6_mul(&acc, f);
+            }
+        }
+    }
+    acc
+}
+
+/// Constant-time scalar multiplication, for secret scalars such as private keys and nonces.
+///
+/// Uses a Montgomery ladder over complete projective formulas: every bit of `n` costs the same
+/// two additions and conditional swaps, and the belt arithmetic below them has no
+/// value-dependent branches. The only branch is the final affine conversion, which tells the
+/// identity apart, i.e. whether `n` is a multiple of the point's order. This holds at the source
+/// level; the generated code is not checked. The result equals [`ch_scal_big`] for points of the
+/// prime-order subgroup.
+pub fn ch_scal_ct(n: &U256, p: &CheetahPoint) -> CheetahPoint {
+    let bytes = n.to_le_bytes();
+    let mut r0 = ProjectivePoint::IDENTITY;
+    let mut r1 = ProjectivePoint::from_affine(p);
+    for i in (0..256).rev() {
+        let bit = ((bytes[i / 8] >> (i % 8)) & 1) as u64;
+        ch_cswap(&mut r0, &mut r1, bit);
+        r1 = r0.add(&r1);
+        r0 = r0.add(&r0);
+        ch_cswap(&mut r0, &mut r1, bit);
+    }
+    r0.to_affine()
+}
+
+/// Fixed-base table for [`A_GEN`]: entry `[i][d]` is `d·16ⁱ·G`.
+type GenTable = [[ProjectivePoint; 16]; 64];
+
+const GEN_TABLE_EMPTY: u8 = 0;
+const GEN_TABLE_BUILDING: u8 = 1;
+const GEN_TABLE_READY: u8 = 2;
+
+struct GenTableCell(UnsafeCell<MaybeUninit<GenTable>>);
+
+// SAFETY: the table is written once by the thread that moves the state from EMPTY to BUILDING,
+// and only read after the state is READY.
+unsafe impl Sync for GenTableCell {}
+
+static GEN_TABLE: GenTableCell = GenTableCell(UnsafeCell::new(MaybeUninit::uninit()));
+static GEN_TABLE_STATE: AtomicU8 = AtomicU8::new(GEN_TABLE_EMPTY);
+
+/// The generator table, building it on first use.
+///
+/// Returns `None` while another thread is still building it.
+fn gen_table() -> Option<&'static GenTable> {
+    match GEN_TABLE_STATE.compare_exchange(
+        GEN_TABLE_EMPTY,
+        GEN_TABLE_BUILDING,
+        Ordering::Acquire,
+        Ordering::Acquire,
+    ) {
+        Ok(_) => {
+            // SAFETY: we won the race to build the table, so nobody else accesses it until
+            // READY is published below. Entries are written in place to keep the ~150KB table
+            // off the stack.
+            let table = unsafe { &mut *(*GEN_TABLE.0.get()).as_mut_ptr() };
+            let mut base = ProjectivePoint::from_affine(&A_GEN);
+            for row in table.iter_mut() {
+                let mut acc = ProjectivePoint::IDENTITY;
+                for entry in row.iter_mut() {
+                    *entry = acc;
+                    acc = acc.add(&base);
+                }
+                base = acc;
+            }
+            GEN_TABLE_STATE.store(GEN_TABLE_READY, Ordering::Release);
+        }
+        Err(GEN_TABLE_BUILDING) => return None,
+        Err(_) => {}
+    }
+    // SAFETY: the state is READY, so the table is fully initialized and never written again.
+    Some(unsafe { &*(*GEN_TABLE.0.get()).as_ptr() })
+}
+
+/// Constant-time multiplication of the generator, `n·G`.
+///
+/// Uses a lazily built fixed-base table of 4-bit windows, selecting each entry with a full
+/// constant-time scan, with the same caveats as [`ch_scal_ct`]. Much faster than it once the
+/// table exists; falls back to it if another thread is still building the table.
+pub fn ch_scal_base(n: &U256) -> CheetahPoint {
+    let Some(table) = gen_table() else {
+        return ch_scal_ct(n, &A_GEN);
+    };
+    let bytes = n.to_le_bytes();
+    let mut acc = ProjectivePoint::IDENTITY;
+    for (i, row) in table.iter().enumerate() {
+        let digit = ((bytes[i / 2] >> (4 * (i % 2))) & 0xf) as u64;
+        let mut entry = ProjectivePoint::IDENTITY;
+        for (d, candidate) in row.iter().enumerate() {
+            // 1 if d == digit, without branching on the digit
+            let eq = ((d as u64 ^ digit).wrapping_sub(1) >> 63) & 1;
+            ch_cmov(&mut entry, candidate, eq);
+        }
+        acc = acc.add(&entry);
+    }
+    acc.to_affine()
+}
+
 pub fn trunc_g_order(a: &[u64]) -> U256 {
     let mut result = U256::from_u64(a[0]);
 
@@ -464,3 +709,31 @@ pub fn trunc_g_order(a: &[u64]) -> U256 {
     let term3 = MulMod::mul_mod(&P_BIG_3, &U256::from_u64(a[3]), &G_ORDER);
     result.add_mod(&term3, &G_ORDER)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ch_scal_ct() {
+        let scalars = [
+            U256::ZERO,
+            U256::ONE,
+            U256::from_u64(2),
+            U256::from_u64(0xdead_beef_cafe_babe),
+            U256::from_be_hex("4c1d6a8e0f3b27a95e7c2d4f6a8b0c1e3d5f7a9b2c4e6f8a0b1c3d5e7f9a2b4c"),
+            G_ORDER.wrapping_sub(&U256::ONE),
+            G_ORDER,
+            U256::MAX,
+        ];
+        let points = [A_GEN, ch_scal(7, &A_GEN).unwrap(), A_ID];
+        for p in &points {
+            for n in &scalars {
+                assert_eq!(ch_scal_ct(n, p), ch_scal_big(n, p).unwrap());
+            }
+        }
+        for n in &scalars {
+            assert_eq!(ch_scal_base(n), ch_scal_big(n, &A_GEN).unwrap());
+        }
+    }
+}
@@ -16,6 +16,8 @@ use serde::{Deserialize, Deserializer};
 
 use crate::{belt::Belt, crypto::cheetah::CheetahPoint, Digest};
 
+pub mod text;
+
 /// A transparent wrapper that encodes as a zero atom if the value is `None`.
 #[repr(transparent)]
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -76,10 +78,14 @@ fn mug_noun(noun: &Noun) -> u32 {
     }
 }
 
-fn weight_noun(noun: &Noun) -> u32 {
+/// Number of atoms and cells in the noun, with shared subnouns counted once per reference.
+/// Saturates, as a few nested backreferences are enough to overflow it.
+fn weight_noun(noun: &Noun) -> u64 {
     match noun {
         Noun::Atom(_) => 1,
-        Noun::Cell(left, right) => 1 + left.0.weight + right.0.weight,
+        Noun::Cell(left, right) => 1u64
+            .saturating_add(left.0.weight)
+            .saturating_add(right.0.weight),
     }
 }
 
@@ -87,7 +93,7 @@ fn weight_noun(noun: &Noun) -> u32 {
 struct HashNounContents {
     noun: Noun,
     mug: u32,
-    weight: u32,
+    weight: u64,
 }
 
 impl From<Noun> for HashNounContents {
@@ -100,6 +106,30 @@ impl From<Noun> for HashNounContents {
     }
 }
 
+impl Drop for HashNounContents {
+    /// Drops deep nouns without recursing, so a long list or a deeply nested cue result does not
+    /// overflow the stack. Subnouns that are still shared elsewhere are left to their other owners.
+    fn drop(&mut self) {
+        fn take_children(noun: &mut Noun, stack: &mut Vec<Noun>) {
+            if let Noun::Cell(left, right) = noun {
+                for child in [left, right] {
+                    if let Some(contents) = Arc::get_mut(&mut child.0) {
+                        if let Noun::Cell(_, _) = contents.noun {
+                            stack.push(core::mem::replace(&mut contents.noun, atom(0)));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut stack = Vec::new();
+        take_children(&mut self.noun, &mut stack);
+        while let Some(mut noun) = stack.pop() {
+            take_children(&mut noun, &mut stack);
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct HashNoun(Arc<HashNounContents>);
 
@@ -663,8 +693,8 @@ pub fn jam(noun: Noun) -> Vec<u8> {
     }
 
     fn find_backref(
-        backrefs: &BTreeMap<(u32, u32), Vec<(Noun, usize)>>,
-        weight: u32,
+        backrefs: &BTreeMap<(u64, u32), Vec<(Noun, usize)>>,
+        weight: u64,
         mug: u32,
         target: &Noun,
     ) -> Option<usize> {
@@ -674,7 +704,7 @@ pub fn jam(noun: Noun) -> Vec<u8> {
             .map(|(_, offset)| *offset)
     }
 
-    let mut backrefs: BTreeMap<(u32, u32), Vec<(Noun, usize)>> = BTreeMap::new();
+    let mut backrefs: BTreeMap<(u64, u32), Vec<(Noun, usize)>> = BTreeMap::new();
     let mut stack = Vec::new();
     stack.push((weight_noun(&noun), mug_noun(&noun), noun));
     let mut buffer = BitWriter::new();
@@ -722,144 +752,280 @@ pub fn cue(bytes: &[u8]) -> Option<Noun> {
 }
 
 pub fn cue_bitslice(buffer: &BitSlice<u8, Lsb0>) -> Option<Noun> {
-    #[derive(Copy, Clone)]
-    enum CueStackEntry {
-        DestinationPointer(*mut Noun),
-        BackRef(u64, *mut Noun),
-    }
-
-    pub fn next_up_to_n_bits<'a>(
-        cursor: &mut usize,
-        slice: &'a BitSlice<u8, Lsb0>,
-        n: usize,
-    ) -> &'a BitSlice<u8, Lsb0> {
-        let res = if (slice).len() >= *cursor + n {
-            &slice[*cursor..*cursor + n]
-        } else if slice.len() > *cursor {
-            &slice[*cursor..]
-        } else {
-            BitSlice::<u8, Lsb0>::empty()
-        };
-        *cursor += n;
-        res
-    }
+    cue_bitslice_with_limits(buffer, &CueLimits::UNLIMITED).ok()
+}
 
-    pub fn rest_bits(cursor: usize, slice: &BitSlice<u8, Lsb0>) -> &BitSlice<u8, Lsb0> {
-        if slice.len() > cursor {
-            &slice[cursor..]
-        } else {
-            BitSlice::<u8, Lsb0>::empty()
+/// Bounds on the noun produced by [`cue_with_limits`].
+///
+/// The default limits are meant for untrusted input, such as responses from a peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CueLimits {
+    /// Maximum nesting of cells
+    pub max_depth: usize,
+    /// Maximum size of a single atom, in bits
+    pub max_atom_bits: usize,
+    /// Maximum number of atoms, cells and backreferences decoded
+    pub max_nodes: usize,
+    /// Maximum number of backreferences decoded. Backreferenced nouns are shared, but consumers
+    /// walking the noun will visit them once per reference.
+    pub max_backrefs: usize,
+    /// Maximum number of atoms and cells in the noun once backreferences are expanded
+    pub max_expanded_nodes: u64,
+}
+
+impl CueLimits {
+    pub const UNLIMITED: Self = Self {
+        max_depth: usize::MAX,
+        max_atom_bits: usize::MAX,
+        max_nodes: usize::MAX,
+        max_backrefs: usize::MAX,
+        max_expanded_nodes: u64::MAX,
+    };
+}
+
+impl Default for CueLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 1 << 20,
+            max_atom_bits: 1 << 27,
+            max_nodes: 1 << 24,
+            max_backrefs: 1 << 22,
+            max_expanded_nodes: 1 << 28,
         }
     }
+}
 
-    fn get_size(cursor: &mut usize, buffer: &BitSlice<u8, Lsb0>) -> Option<usize> {
-        let buff_at_cursor = rest_bits(*cursor, buffer);
-        let bitsize = buff_at_cursor.first_one()?;
-        if bitsize == 0 {
-            *cursor += 1;
-            Some(0)
-        } else {
-            let mut size = [0u8; 8];
-            *cursor += bitsize + 1;
-            let size_bits = next_up_to_n_bits(cursor, buffer, bitsize - 1);
-            BitSlice::from_slice_mut(&mut size)[0..bitsize - 1].copy_from_bitslice(size_bits);
-            Some((u64::from_le_bytes(size) as usize) + (1 << (bitsize - 1)))
+/// Reason `cue` failed, with the bit offset into the input where it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CueError {
+    /// Input ended in the middle of a noun
+    UnexpectedEnd {
+        offset: usize,
+    },
+    /// Length prefix too large to be valid
+    InvalidLength {
+        offset: usize,
+    },
+    /// Backreference to an offset that holds no complete noun
+    InvalidBackref {
+        offset: usize,
+        backref: u64,
+    },
+    AtomTooLarge {
+        offset: usize,
+        bits: usize,
+    },
+    DepthLimit {
+        offset: usize,
+    },
+    NodeLimit {
+        offset: usize,
+    },
+    BackrefLimit {
+        offset: usize,
+    },
+    ExpandedSizeLimit {
+        offset: usize,
+    },
+}
+
+impl CueError {
+    pub fn offset(&self) -> usize {
+        match self {
+            CueError::UnexpectedEnd { offset }
+            | CueError::InvalidLength { offset }
+            | CueError::InvalidBackref { offset, .. }
+            | CueError::AtomTooLarge { offset, .. }
+            | CueError::DepthLimit { offset }
+            | CueError::NodeLimit { offset }
+            | CueError::BackrefLimit { offset }
+            | CueError::ExpandedSizeLimit { offset } => *offset,
         }
     }
+}
 
-    fn rub_backref(cursor: &mut usize, buffer: &BitSlice<u8, Lsb0>) -> Option<u64> {
-        // TODO: What's size here usually?
-        let size = get_size(cursor, buffer)?;
-        if size == 0 {
-            Some(0)
-        } else if size <= 64 {
-            // TODO: Size <= 64, so we can fit the backref in a direct atom?
-            let mut backref = [0u8; 8];
-            BitSlice::from_slice_mut(&mut backref)[0..size]
-                .copy_from_bitslice(&buffer[*cursor..*cursor + size]);
-            *cursor += size;
-            Some(u64::from_le_bytes(backref))
-        } else {
-            None
+impl core::fmt::Display for CueError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CueError::UnexpectedEnd { offset } => {
+                write!(f, "Unexpected end of input at bit {offset}")
+            }
+            CueError::InvalidLength { offset } => {
+                write!(f, "Invalid length prefix at bit {offset}")
+            }
+            CueError::InvalidBackref { offset, backref } => {
+                write!(f, "Invalid backreference to bit {backref} at bit {offset}")
+            }
+            CueError::AtomTooLarge { offset, bits } => {
+                write!(f, "Atom of {bits} bits exceeds limit at bit {offset}")
+            }
+            CueError::DepthLimit { offset } => {
+                write!(f, "Cell depth limit exceeded at bit {offset}")
+            }
+            CueError::NodeLimit { offset } => write!(f, "Node limit exceeded at bit {offset}"),
+            CueError::BackrefLimit { offset } => {
+                write!(f, "Backreference limit exceeded at bit {offset}")
+            }
+            CueError::ExpandedSizeLimit { offset } => {
+                write!(f, "Expanded noun size limit exceeded at bit {offset}")
+            }
         }
     }
+}
 
-    fn rub_atom(cursor: &mut usize, buffer: &BitSlice<u8, Lsb0>) -> Option<UBig> {
-        let size = get_size(cursor, buffer)?;
-        let bits = next_up_to_n_bits(cursor, buffer, size);
-        if size == 0 {
-            Some(UBig::from(0u64))
-        } else if size < 64 {
-            // Fits in a direct atom
-            let mut direct_raw = [0u8; 8];
-            BitSlice::from_slice_mut(&mut direct_raw)[0..bits.len()].copy_from_bitslice(bits);
-            Some(UBig::from(u64::from_le_bytes(direct_raw)))
-        } else {
-            // Need an indirect atom
-            let wordsize = (size + 63) >> 6;
-            let mut bytes = vec![0u8; wordsize * 8];
-            BitSlice::from_slice_mut(&mut bytes)[0..bits.len()].copy_from_bitslice(bits);
-            Some(UBig::from_le_bytes(&bytes))
+impl core::error::Error for CueError {}
+
+/// Cue jammed bytes into Noun, failing once any of `limits` is exceeded
+pub fn cue_with_limits(bytes: &[u8], limits: &CueLimits) -> Result<Noun, CueError> {
+    cue_bitslice_with_limits(BitSlice::from_slice(bytes), limits)
+}
+
+pub fn cue_bitslice_with_limits(
+    buffer: &BitSlice<u8, Lsb0>,
+    limits: &CueLimits,
+) -> Result<Noun, CueError> {
+    struct Reader<'a> {
+        buffer: &'a BitSlice<u8, Lsb0>,
+        cursor: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn bit(&mut self) -> Result<bool, CueError> {
+            let bit = *self
+                .buffer
+                .get(self.cursor)
+                .ok_or(CueError::UnexpectedEnd {
+                    offset: self.cursor,
+                })?;
+            self.cursor += 1;
+            Ok(bit)
         }
-    }
 
-    pub fn next_bit(cursor: &mut usize, slice: &BitSlice<u8, Lsb0>) -> bool {
-        if (*slice).len() > *cursor {
-            let res = slice[*cursor];
-            *cursor += 1;
-            res
-        } else {
-            false
+        fn bits(&mut self, n: usize) -> Result<&'a BitSlice<u8, Lsb0>, CueError> {
+            let end = self
+                .cursor
+                .checked_add(n)
+                .filter(|end| *end <= self.buffer.len())
+                .ok_or(CueError::UnexpectedEnd {
+                    offset: self.buffer.len(),
+                })?;
+            let bits = &self.buffer[self.cursor..end];
+            self.cursor = end;
+            Ok(bits)
+        }
+
+        fn u64(&mut self, n: usize) -> Result<u64, CueError> {
+            let mut raw = [0u8; 8];
+            let bits = self.bits(n)?;
+            BitSlice::<u8, Lsb0>::from_slice_mut(&mut raw)[..n].copy_from_bitslice(bits);
+            Ok(u64::from_le_bytes(raw))
+        }
+
+        /// Length prefix: unary length of the length, then the length without its top bit.
+        fn size(&mut self) -> Result<usize, CueError> {
+            let offset = self.cursor;
+            let rest = &self.buffer[self.cursor.min(self.buffer.len())..];
+            let bitsize = rest.first_one().ok_or(CueError::UnexpectedEnd {
+                offset: self.buffer.len(),
+            })?;
+            self.cursor += bitsize + 1;
+            if bitsize == 0 {
+                return Ok(0);
+            }
+            if bitsize > 64 {
+                return Err(CueError::InvalidLength { offset });
+            }
+            let low = self.u64(bitsize - 1)?;
+            usize::try_from(low | (1 << (bitsize - 1)))
+                .map_err(|_| CueError::InvalidLength { offset })
+        }
+
+        fn atom(&mut self, max_bits: usize) -> Result<UBig, CueError> {
+            let offset = self.cursor;
+            let size = self.size()?;
+            if size > max_bits {
+                return Err(CueError::AtomTooLarge { offset, bits: size });
+            }
+            if size <= 64 {
+                return Ok(UBig::from(self.u64(size)?));
+            }
+            let bits = self.bits(size)?;
+            let mut bytes = vec![0u8; size.div_ceil(8)];
+            BitSlice::<u8, Lsb0>::from_slice_mut(&mut bytes)[..size].copy_from_bitslice(bits);
+            Ok(UBig::from_le_bytes(&bytes))
+        }
+
+        fn backref(&mut self) -> Result<u64, CueError> {
+            let offset = self.cursor;
+            let size = self.size()?;
+            if size > 64 {
+                return Err(CueError::InvalidLength { offset });
+            }
+            self.u64(size)
         }
     }
 
-    let mut backref_map = BTreeMap::<u64, *mut Noun>::new();
-    let mut result = atom(0);
-    let mut cursor = 0;
-
-    let mut cue_stack = vec![];
-
-    cue_stack.push(CueStackEntry::DestinationPointer(&mut result as *mut Noun));
-
-    while let Some(stack_entry) = cue_stack.pop() {
-        unsafe {
-            // Capture the destination pointer and pop it off the stack
-            match stack_entry {
-                CueStackEntry::DestinationPointer(dest_ptr) => {
-                    // 1 bit
-                    if next_bit(&mut cursor, buffer) {
-                        // 11 tag: backref
-                        if next_bit(&mut cursor, buffer) {
-                            let backref = rub_backref(&mut cursor, buffer)?;
-                            *dest_ptr = (**backref_map.get(&backref)?).clone();
-                        } else {
-                            // 10 tag: cell
-                            let head = HashNoun::from(atom(0));
-                            let head_ptr = Arc::as_ptr(&head.0) as *mut _;
-                            let tail = HashNoun::from(atom(0));
-                            let tail_ptr = Arc::as_ptr(&tail.0) as *mut _;
-                            *dest_ptr = Noun::Cell(head, tail);
-                            let backref = (cursor - 2) as u64;
-                            backref_map.insert(backref, dest_ptr);
-                            cue_stack.push(CueStackEntry::BackRef(cursor as u64 - 2, dest_ptr));
-                            cue_stack.push(CueStackEntry::DestinationPointer(tail_ptr));
-                            cue_stack.push(CueStackEntry::DestinationPointer(head_ptr));
-                        }
-                    } else {
-                        // 0 tag: atom
-                        let backref: u64 = (cursor - 1) as u64;
-                        *dest_ptr = Noun::Atom(rub_atom(&mut cursor, buffer)?);
-                        backref_map.insert(backref, dest_ptr);
-                    }
+    let mut reader = Reader { buffer, cursor: 0 };
+    // Cells being decoded: their offset, and their head once it is complete
+    let mut stack: Vec<(usize, Option<HashNoun>)> = vec![];
+    // Every noun is decoded into an `Arc` once, and shared by all of its backreferences
+    let mut decoded = BTreeMap::<u64, HashNoun>::new();
+    let mut nodes = 0usize;
+    let mut backrefs = 0usize;
+
+    loop {
+        let offset = reader.cursor;
+        nodes += 1;
+        if nodes > limits.max_nodes {
+            return Err(CueError::NodeLimit { offset });
+        }
+
+        let mut noun = if !reader.bit()? {
+            // 0 tag: atom
+            let noun = HashNoun::from(Noun::Atom(reader.atom(limits.max_atom_bits)?));
+            decoded.insert(offset as u64, noun.clone());
+            noun
+        } else if !reader.bit()? {
+            // 10 tag: cell
+            if stack.len() >= limits.max_depth {
+                return Err(CueError::DepthLimit { offset });
+            }
+            stack.push((offset, None));
+            continue;
+        } else {
+            // 11 tag: backref
+            backrefs += 1;
+            if backrefs > limits.max_backrefs {
+                return Err(CueError::BackrefLimit { offset });
+            }
+            let backref = reader.backref()?;
+            decoded
+                .get(&backref)
+                .cloned()
+                .ok_or(CueError::InvalidBackref { offset, backref })?
+        };
+
+        // Attach the finished noun to its parent, completing every cell whose tail it was.
+        loop {
+            match stack.last_mut() {
+                None => return Ok((*noun).clone()),
+                Some((_, head @ None)) => {
+                    *head = Some(noun);
+                    break;
                 }
-                CueStackEntry::BackRef(backref, noun_ptr) => {
-                    backref_map.insert(backref, noun_ptr);
+                Some((_, Some(_))) => {
+                    let Some((offset, Some(head))) = stack.pop() else {
+                        unreachable!();
+                    };
+                    noun = HashNoun::from(Noun::Cell(head, noun));
+                    if noun.0.weight > limits.max_expanded_nodes {
+                        return Err(CueError::ExpandedSizeLimit { offset });
+                    }
+                    decoded.insert(offset as u64, noun.clone());
                 }
             }
         }
     }
-
-    Some(result)
 }
 
 // Fast bit writer that appends bits LSB-first into an underlying Vec<u8>
@@ -1023,3 +1189,148 @@ impl BitWriter {
         self.buf
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cue_roundtrip() {
+        let shared = (1u64, (u64::MAX, "a longer atom than sixty-four bits")).to_noun();
+        let noun = (shared.clone(), (shared.clone(), (shared, 0u64))).to_noun();
+        let jammed = jam(noun.clone());
+
+        let cued = cue_with_limits(&jammed, &CueLimits::default()).unwrap();
+        assert_eq!(cued, noun);
+        assert_eq!(cue(&jammed).unwrap(), noun);
+        // Mugs and weights of cued nouns must be intact for jam to find backrefs again
+        assert_eq!(jam(cued), jammed);
+    }
+
+    #[test]
+    fn test_cue_limits() {
+        let list = (1u64, (2u64, (3u64, (4u64, 0u64)))).to_noun();
+        let jammed = jam(list.clone());
+        let limits = CueLimits::default();
+
+        assert!(matches!(
+            cue_with_limits(
+                &jammed,
+                &CueLimits {
+                    max_depth: 3,
+                    ..limits
+                }
+            ),
+            Err(CueError::DepthLimit { .. })
+        ));
+        assert_eq!(
+            cue_with_limits(
+                &jammed,
+                &CueLimits {
+                    max_depth: 4,
+                    ..limits
+                }
+            ),
+            Ok(list)
+        );
+        assert!(matches!(
+            cue_with_limits(
+                &jammed,
+                &CueLimits {
+                    max_nodes: 8,
+                    ..limits
+                }
+            ),
+            Err(CueError::NodeLimit { .. })
+        ));
+
+        let jammed = jam("some atom".to_noun());
+        assert_eq!(
+            cue_with_limits(
+                &jammed,
+                &CueLimits {
+                    max_atom_bits: 8,
+                    ..limits
+                }
+            ),
+            Err(CueError::AtomTooLarge {
+                offset: 1,
+                bits: 71
+            })
+        );
+
+        let cell = (u64::MAX, u64::MAX).to_noun();
+        let jammed = jam((cell.clone(), cell).to_noun());
+        assert!(matches!(
+            cue_with_limits(
+                &jammed,
+                &CueLimits {
+                    max_backrefs: 1,
+                    ..limits
+                }
+            ),
+            Err(CueError::BackrefLimit { .. })
+        ));
+
+        // 40 nested cells, each tail a backreference to its head, expand to 2^41 nodes
+        let hostile: [u8; 78] = [
+            0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x8e, 0x43, 0xc7, 0x9d,
+            0xe3, 0xcc, 0x71, 0xe5, 0x38, 0x72, 0xdc, 0x38, 0x4e, 0x1c, 0x17, 0x8e, 0x03, 0x47,
+            0xfd, 0x51, 0x7e, 0x54, 0x1f, 0xc5, 0x47, 0xed, 0x51, 0x7a, 0x54, 0x1e, 0x85, 0x47,
+            0xdd, 0x51, 0x76, 0x54, 0x1d, 0x45, 0x47, 0xcd, 0x51, 0x72, 0x54, 0x1c, 0x05, 0xc7,
+            0xfc, 0x18, 0x1f, 0xd3, 0x63, 0x78, 0xcc, 0x8e, 0xd1, 0x31, 0x39, 0x06, 0x47, 0x7c,
+            0x84, 0x47, 0x74, 0x04, 0x67, 0xcf, 0x9c, 0x04,
+        ];
+        assert_eq!(
+            cue_with_limits(&hostile, &limits),
+            Err(CueError::ExpandedSizeLimit { offset: 24 })
+        );
+        assert!(cue(&hostile).is_some());
+    }
+
+    #[test]
+    fn test_cue_deep_drop() {
+        // Cued with the default limits, a long list must be dropped without recursing
+        let list = vec![1u64; (1 << 20) - 1].to_noun();
+        let jammed = jam(list);
+        let cued = cue_with_limits(&jammed, &CueLimits::default()).unwrap();
+        drop(cued);
+
+        // Shared subnouns survive the drop of one of their owners
+        let shared = vec![2u64; 1 << 16].to_noun();
+        let pair = (shared.clone(), shared.clone()).to_noun();
+        drop(pair);
+        assert_eq!(Vec::<u64>::from_noun(&shared), Some(vec![2u64; 1 << 16]));
+    }
+
+    #[test]
+    fn test_cue_malformed() {
+        let jammed = jam("some atom".to_noun());
+        let err = cue_with_limits(&jammed[..4], &CueLimits::UNLIMITED).unwrap_err();
+        assert_eq!(err, CueError::UnexpectedEnd { offset: 32 });
+        assert_eq!(cue(&jammed[..4]), None);
+
+        // A length prefix of 100 zeros used to panic
+        let mut hostile = vec![0u8; 13];
+        hostile.push(0xff);
+        assert_eq!(
+            cue_with_limits(&hostile, &CueLimits::UNLIMITED),
+            Err(CueError::InvalidLength { offset: 1 })
+        );
+
+        // Backref (tag `11`, length `01`, then bit `1`) to offset 1, as the first noun
+        assert_eq!(
+            cue_with_limits(&[0b11011], &CueLimits::UNLIMITED),
+            Err(CueError::InvalidBackref {
+                offset: 0,
+                backref: 1
+            })
+        );
+        assert_eq!(
+            cue_with_limits(&[], &CueLimits::UNLIMITED)
+                .unwrap_err()
+                .offset(),
+            0
+        );
+    }
+}
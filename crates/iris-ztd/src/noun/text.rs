@@ -0,0 +1,406 @@
+//! Hoon-style text notation for nouns.
+//!
+//! Cells print as `[a b c]`, null-terminated lists as `~[a b]`, and atoms as `%term`, `'cord'`,
+//! dotted decimal (`1.000`) or dotted hex (`0x1.0000`), whichever reads best. Tapes print as
+//! `"text"`. [`parse`] reads the same notation back, so `parse(&print(n)) == n`.
+//!
+//! Text detection is a heuristic: a number that happens to consist of printable bytes will be
+//! printed as a cord.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Write};
+use ibig::UBig;
+use num_traits::Zero;
+
+use super::Noun;
+
+/// Atoms of up to this many bits are printed in decimal, larger ones in hex.
+const DECIMAL_MAX_BITS: usize = 64;
+
+/// Displays a noun in Hoon notation.
+pub struct Hoon<'a>(pub &'a Noun);
+
+impl Display for Hoon<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_noun(f, self.0)
+    }
+}
+
+/// Print a noun in Hoon notation.
+pub fn print(noun: &Noun) -> String {
+    Hoon(noun).to_string()
+}
+
+fn write_noun(f: &mut impl Write, noun: &Noun) -> fmt::Result {
+    let (head, tail) = match noun {
+        Noun::Atom(a) => return write_atom(f, a),
+        Noun::Cell(head, tail) => (head, tail),
+    };
+
+    // Walk the right spine to see if this is a null-terminated list
+    let mut items = alloc::vec![&**head];
+    let mut rest = &**tail;
+    while let Noun::Cell(h, t) = rest {
+        items.push(&**h);
+        rest = &**t;
+    }
+    let null_terminated = matches!(rest, Noun::Atom(a) if a.is_zero());
+
+    if null_terminated {
+        if let Some(tape) = as_tape(&items) {
+            return write_quoted(f, '"', &tape);
+        }
+        f.write_str("~[")?;
+    } else {
+        f.write_char('[')?;
+    }
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_char(' ')?;
+        }
+        write_noun(f, item)?;
+    }
+    if !null_terminated {
+        f.write_char(' ')?;
+        write_noun(f, rest)?;
+    }
+    f.write_char(']')
+}
+
+fn as_tape(items: &[&Noun]) -> Option<String> {
+    if items.len() < 2 {
+        return None;
+    }
+    items
+        .iter()
+        .map(|item| match item {
+            Noun::Atom(a) => {
+                let b = u8::try_from(a).ok()?;
+                is_printable(b).then_some(b as char)
+            }
+            Noun::Cell(..) => None,
+        })
+        .collect()
+}
+
+fn is_printable(b: u8) -> bool {
+    (0x20..0x7f).contains(&b)
+}
+
+fn is_term(bytes: &[u8]) -> bool {
+    bytes.first().is_some_and(u8::is_ascii_lowercase)
+        && bytes.last() != Some(&b'-')
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'-')
+}
+
+fn write_atom(f: &mut impl Write, atom: &UBig) -> fmt::Result {
+    let bytes = atom.to_le_bytes();
+    if bytes.len() >= 2 {
+        if is_term(&bytes) {
+            f.write_char('%')?;
+            // Terms are ASCII by construction
+            return f.write_str(core::str::from_utf8(&bytes).map_err(|_| fmt::Error)?);
+        }
+        if bytes.iter().all(|b| is_printable(*b)) {
+            let text = bytes.iter().map(|b| *b as char).collect::<String>();
+            return write_quoted(f, '\'', &text);
+        }
+    }
+
+    if atom.bit_len() <= DECIMAL_MAX_BITS {
+        write_dotted(f, "", &atom.to_string(), 3)
+    } else {
+        write_dotted(f, "0x", &alloc::format!("{atom:x}"), 4)
+    }
+}
+
+fn write_dotted(f: &mut impl Write, prefix: &str, digits: &str, group: usize) -> fmt::Result {
+    f.write_str(prefix)?;
+    let first = match digits.len() % group {
+        0 => group,
+        n => n,
+    };
+    f.write_str(&digits[..first.min(digits.len())])?;
+    let mut rest = &digits[first.min(digits.len())..];
+    while !rest.is_empty() {
+        f.write_char('.')?;
+        f.write_str(&rest[..group])?;
+        rest = &rest[group..];
+    }
+    Ok(())
+}
+
+fn write_quoted(f: &mut impl Write, quote: char, text: &str) -> fmt::Result {
+    f.write_char(quote)?;
+    for c in text.chars() {
+        if c == quote || c == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char(quote)
+}
+
+/// Error from [`parse`], with the byte offset into the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.reason, self.offset)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// Parse a noun in Hoon notation.
+///
+/// Accepts everything [`print`] produces, plus `~` for the null atom.
+pub fn parse(src: &str) -> Result<Noun, ParseError> {
+    let mut parser = Parser {
+        src: src.as_bytes(),
+        pos: 0,
+    };
+    parser.skip_ws();
+    let noun = parser.noun()?;
+    parser.skip_ws();
+    if parser.pos != parser.src.len() {
+        return Err(parser.error("Trailing input"));
+    }
+    Ok(noun)
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &'static str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn noun(&mut self) -> Result<Noun, ParseError> {
+        match self.peek() {
+            Some(b'[') => {
+                self.pos += 1;
+                self.cell(false)
+            }
+            Some(b'~') => {
+                self.pos += 1;
+                if self.peek() == Some(b'[') {
+                    self.pos += 1;
+                    self.cell(true)
+                } else {
+                    Ok(Noun::Atom(UBig::from(0u64)))
+                }
+            }
+            Some(b'%') => {
+                self.pos += 1;
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+                {
+                    self.pos += 1;
+                }
+                if !is_term(&self.src[start..self.pos]) {
+                    return Err(self.error("Invalid term"));
+                }
+                Ok(Noun::Atom(UBig::from_le_bytes(&self.src[start..self.pos])))
+            }
+            Some(b'\'') => Ok(Noun::Atom(UBig::from_le_bytes(&self.quoted(b'\'')?))),
+            Some(b'"') => {
+                let text = self.quoted(b'"')?;
+                Ok(text
+                    .iter()
+                    .rev()
+                    .fold(Noun::Atom(UBig::from(0u64)), |tail, b| {
+                        Noun::Cell(Noun::Atom(UBig::from(*b)).into(), tail.into())
+                    }))
+            }
+            Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    /// Parse cell items after the opening bracket, right-nesting them.
+    fn cell(&mut self, null_terminated: bool) -> Result<Noun, ParseError> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                break;
+            }
+            if !items.is_empty() && !self.src[self.pos - 1].is_ascii_whitespace() {
+                return Err(self.error("Expected whitespace between cell items"));
+            }
+            items.push(self.noun()?);
+        }
+
+        let mut items = items.into_iter().rev();
+        let last = if null_terminated {
+            Noun::Atom(UBig::from(0u64))
+        } else {
+            if items.len() < 2 {
+                return Err(self.error("Cell needs at least two items"));
+            }
+            items.next().expect("checked length")
+        };
+        if items.len() == 0 {
+            return Err(self.error("List needs at least one item"));
+        }
+        Ok(items.fold(last, |tail, head| Noun::Cell(head.into(), tail.into())))
+    }
+
+    fn quoted(&mut self, quote: u8) -> Result<Vec<u8>, ParseError> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated text")),
+                Some(b) if b == quote => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(b) if b == quote || b == b'\\' => {
+                            out.push(b);
+                            self.pos += 1;
+                        }
+                        Some(_) => {
+                            // `\XX` hex escape
+                            let hex = self
+                                .src
+                                .get(self.pos..self.pos + 2)
+                                .and_then(|h| core::str::from_utf8(h).ok())
+                                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                                .ok_or_else(|| self.error("Invalid escape"))?;
+                            out.push(hex);
+                            self.pos += 2;
+                        }
+                        None => return Err(self.error("Unterminated text")),
+                    }
+                }
+                Some(b) => {
+                    out.push(b);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Noun, ParseError> {
+        let start = self.pos;
+        let (radix, digits_start) = if self.src[self.pos..].starts_with(b"0x") {
+            (16, self.pos + 2)
+        } else {
+            (10, self.pos)
+        };
+        self.pos = digits_start;
+        let mut digits = String::new();
+        while let Some(b) = self.peek() {
+            if (b as char).is_digit(radix) {
+                digits.push(b as char);
+            } else if b == b'.' && !digits.is_empty() {
+                // Dots group digits, and may not trail
+                if !self
+                    .src
+                    .get(self.pos + 1)
+                    .is_some_and(|b| (*b as char).is_digit(radix))
+                {
+                    return Err(self.error("Misplaced dot in number"));
+                }
+            } else {
+                break;
+            }
+            self.pos += 1;
+        }
+        if digits.is_empty() {
+            self.pos = start;
+            return Err(self.error("Expected digits"));
+        }
+        UBig::from_str_radix(&digits, radix)
+            .map(Noun::Atom)
+            .map_err(|_| ParseError {
+                offset: start,
+                reason: "Invalid number",
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NounEncode;
+
+    fn roundtrip(noun: Noun, text: &str) {
+        assert_eq!(print(&noun), text);
+        assert_eq!(parse(text).unwrap(), noun);
+    }
+
+    #[test]
+    fn test_print_parse() {
+        roundtrip(0u64.to_noun(), "0");
+        roundtrip(1_000_000u64.to_noun(), "1.000.000");
+        roundtrip(999u64.to_noun(), "999");
+        roundtrip(u64::MAX.to_noun(), "18.446.744.073.709.551.615");
+        roundtrip(
+            Noun::Atom(UBig::from(1u128 << 64)),
+            "0x1.0000.0000.0000.0000",
+        );
+        roundtrip("tx-id".to_noun(), "%tx-id");
+        roundtrip("Hello, it's".to_noun(), "'Hello, it\\'s'");
+        roundtrip((1u64, 2u64, 3u64).to_noun(), "[1 2 3]");
+        roundtrip(((1u64, 2u64), 3u64).to_noun(), "[[1 2] 3]");
+        roundtrip((1u64, (2u64, 0u64)).to_noun(), "~[1 2]");
+        roundtrip(alloc::vec![104u64, 105u64].to_noun(), "\"hi\"");
+        // A tape in tail position is indistinguishable from a longer list
+        roundtrip(
+            ("lock", alloc::vec![104u64, 105u64]).to_noun(),
+            "~[%lock 104 105]",
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        assert_eq!(parse(" ~ ").unwrap(), 0u64.to_noun());
+        assert_eq!(parse("[1\n  2]").unwrap(), (1u64, 2u64).to_noun());
+        assert_eq!(parse("0xff").unwrap(), 255u64.to_noun());
+        assert_eq!(parse("'\\41'").unwrap(), "A".to_noun());
+        assert_eq!(parse("[~ ~[%a]]").unwrap(), parse("[0 [97 0]]").unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("[1]").unwrap_err().offset, 3);
+        assert_eq!(parse("[1 2").unwrap_err().offset, 4);
+        assert_eq!(parse("1 2").unwrap_err().offset, 2);
+        assert_eq!(parse("1.").unwrap_err().offset, 1);
+        assert_eq!(parse("'abc").unwrap_err().offset, 4);
+        assert_eq!(parse("%Abc").unwrap_err().offset, 1);
+    }
+}
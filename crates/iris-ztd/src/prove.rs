@@ -18,6 +18,18 @@ pub struct MerkleProvenAxis {
     pub axis: u64,
 }
 
+/// Proof that a key is absent from a `ZMap` or `ZSet`.
+///
+/// Shows the empty subtree the key would occupy, together with membership proofs of its
+/// `gor`-ordered neighbours, i.e. the closest entries ordered before and after the key. A missing
+/// neighbour means the key would be ordered first (or last) in the treap.
+#[derive(Debug, Clone, NounEncode, NounDecode, Serialize, Deserialize)]
+pub struct NonMembershipProof<T> {
+    pub empty: MerkleProvenAxis,
+    pub predecessor: Option<(T, MerkleProvenAxis)>,
+    pub successor: Option<(T, MerkleProvenAxis)>,
+}
+
 impl MerkleProof {
     /// Prove a 0-indexed leaf
     ///
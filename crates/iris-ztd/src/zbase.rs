@@ -6,8 +6,8 @@ use alloc::string::ToString;
 use core::borrow::Borrow;
 use serde::de::{SeqAccess, Visitor};
 
-use crate::Zeroable;
 use crate::{Digest, Hashable, Noun, NounDecode, NounEncode};
+use crate::{MerkleProof, MerkleProvenAxis, NonMembershipProof, Zeroable};
 
 use alloc::fmt::Debug;
 use alloc::vec;
@@ -54,6 +54,8 @@ struct Node<E> {
     right: Zeroable<Box<Node<E>>>,
 }
 
+type Tree<E> = Option<Box<Node<E>>>;
+
 impl<E: ZEntry> Default for ZBase<E> {
     fn default() -> Self {
         Self::new()
@@ -195,11 +197,193 @@ impl<E: ZEntry> ZBase<E> {
         }
     }
 
+    /// Remove the entry for `key`, returning it if it was present.
+    ///
+    /// Mirrors Hoon's `+del`: the removed node is replaced by the priority-merge of its children.
+    pub fn remove_entry<Q: NounEncode + ?Sized>(&mut self, key: &Q) -> Option<E>
+    where
+        E::Key: Borrow<Q>,
+    {
+        let (root, removed) = Self::del(self.root.take(), key);
+        self.root = Zeroable(root);
+        removed
+    }
+
+    /// Union of two treaps. Entries of `other` take precedence on equal keys, like Hoon's `+uni`.
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            root: Zeroable(Self::uni(self.root.0, other.root.0)),
+        }
+    }
+
+    /// Entries whose keys are present in both treaps. Entries of `other` are kept, like Hoon's
+    /// `+int`.
+    pub fn intersection(self, other: Self) -> Self {
+        Self {
+            root: Zeroable(Self::int(self.root.0, other.root.0)),
+        }
+    }
+
+    /// Entries of `self` whose keys are not present in `other`, like Hoon's `+dif`.
+    pub fn difference(self, other: Self) -> Self {
+        Self {
+            root: Zeroable(Self::dif(self.root.0, other.root.0)),
+        }
+    }
+
+    /// Split into entries ordered before `key` and entries ordered after it (by `gor-tip`), plus
+    /// the entry for `key` itself, if present. Like Hoon's `+bif`.
+    pub fn split<Q: NounEncode + ?Sized>(self, key: &Q) -> (Self, Option<E>, Self)
+    where
+        E::Key: Borrow<Q>,
+    {
+        let (left, entry, right) = Self::bif(self.root.0, key);
+        (
+            Self {
+                root: Zeroable(left),
+            },
+            entry,
+            Self {
+                root: Zeroable(right),
+            },
+        )
+    }
+
+    fn node(entry: E, left: Tree<E>, right: Tree<E>) -> Box<Node<E>> {
+        Box::new(Node {
+            entry,
+            left: Zeroable(left),
+            right: Zeroable(right),
+        })
+    }
+
+    fn bif<Q: NounEncode + ?Sized>(node: Tree<E>, key: &Q) -> (Tree<E>, Option<E>, Tree<E>) {
+        let Some(mut n) = node else {
+            return (None, None, None);
+        };
+        if Self::tip_eq(key, n.entry.key()) {
+            let Node { entry, left, right } = *n;
+            return (left.0, Some(entry), right.0);
+        }
+        if Self::gor_tip(key, n.entry.key()) {
+            let (left, entry, right) = Self::bif(n.left.take(), key);
+            n.left = Zeroable(right);
+            (left, entry, Some(n))
+        } else {
+            let (left, entry, right) = Self::bif(n.right.take(), key);
+            n.right = Zeroable(left);
+            (Some(n), entry, right)
+        }
+    }
+
+    /// Merge two treaps, where every key of `left` is ordered before every key of `right`.
+    fn join(left: Tree<E>, right: Tree<E>) -> Tree<E> {
+        match (left, right) {
+            (None, r) => r,
+            (l, None) => l,
+            (Some(mut l), Some(mut r)) => {
+                if Self::mor_tip(l.entry.key(), r.entry.key()) {
+                    l.right = Zeroable(Self::join(l.right.take(), Some(r)));
+                    Some(l)
+                } else {
+                    r.left = Zeroable(Self::join(Some(l), r.left.take()));
+                    Some(r)
+                }
+            }
+        }
+    }
+
+    fn del<Q: NounEncode + ?Sized>(node: Tree<E>, key: &Q) -> (Tree<E>, Option<E>) {
+        let Some(mut n) = node else {
+            return (None, None);
+        };
+        if Self::tip_eq(key, n.entry.key()) {
+            let Node { entry, left, right } = *n;
+            return (Self::join(left.0, right.0), Some(entry));
+        }
+        let removed = if Self::gor_tip(key, n.entry.key()) {
+            let (left, removed) = Self::del(n.left.take(), key);
+            n.left = Zeroable(left);
+            removed
+        } else {
+            let (right, removed) = Self::del(n.right.take(), key);
+            n.right = Zeroable(right);
+            removed
+        };
+        (Some(n), removed)
+    }
+
+    fn uni(a: Tree<E>, b: Tree<E>) -> Tree<E> {
+        let (a, b) = match (a, b) {
+            (None, b) => return b,
+            (a, None) => return a,
+            (Some(a), Some(b)) => (a, b),
+        };
+        if Self::mor_tip(a.entry.key(), b.entry.key()) {
+            let Node { entry, left, right } = *a;
+            let (bl, found, br) = Self::bif(Some(b), entry.key());
+            let left = Self::uni(left.0, bl);
+            let right = Self::uni(right.0, br);
+            Some(Self::node(found.unwrap_or(entry), left, right))
+        } else {
+            let Node { entry, left, right } = *b;
+            let (al, _, ar) = Self::bif(Some(a), entry.key());
+            let left = Self::uni(al, left.0);
+            let right = Self::uni(ar, right.0);
+            Some(Self::node(entry, left, right))
+        }
+    }
+
+    fn int(a: Tree<E>, b: Tree<E>) -> Tree<E> {
+        let (Some(a), Some(b)) = (a, b) else {
+            return None;
+        };
+        if Self::mor_tip(a.entry.key(), b.entry.key()) {
+            let Node { entry, left, right } = *a;
+            let (bl, found, br) = Self::bif(Some(b), entry.key());
+            let left = Self::int(left.0, bl);
+            let right = Self::int(right.0, br);
+            match found {
+                Some(entry) => Some(Self::node(entry, left, right)),
+                None => Self::join(left, right),
+            }
+        } else {
+            let Node { entry, left, right } = *b;
+            let (al, found, ar) = Self::bif(Some(a), entry.key());
+            let left = Self::int(al, left.0);
+            let right = Self::int(ar, right.0);
+            match found {
+                Some(_) => Some(Self::node(entry, left, right)),
+                None => Self::join(left, right),
+            }
+        }
+    }
+
+    fn dif(a: Tree<E>, b: Tree<E>) -> Tree<E> {
+        let (a, b) = match (a, b) {
+            (None, _) => return None,
+            (a, None) => return a,
+            (Some(a), b) => (a, b),
+        };
+        let Node { entry, left, right } = *a;
+        let (bl, found, br) = Self::bif(b, entry.key());
+        let left = Self::dif(left.0, bl);
+        let right = Self::dif(right.0, br);
+        match found {
+            Some(_) => Self::join(left, right),
+            None => Some(Self::node(entry, left, right)),
+        }
+    }
+
     fn tip_eq<Q: NounEncode + ?Sized>(a: &Q, b: &E::Key) -> bool {
         a.to_noun().hash() == b.to_noun().hash()
     }
 
     fn gor_tip<Q: NounEncode + ?Sized>(a: &Q, b: &E::Key) -> bool {
+        Self::tip_lt(a, b)
+    }
+
+    fn tip_lt<A: NounEncode + ?Sized, B: NounEncode + ?Sized>(a: &A, b: &B) -> bool {
         a.to_noun().hash().to_bytes() < b.to_noun().hash().to_bytes()
     }
 
@@ -222,18 +406,19 @@ impl<E: ZEntry> core::iter::FromIterator<E::Pair> for ZBase<E> {
     }
 }
 
+fn hash_node<E: ZHashableEntry>(node: &Zeroable<Box<Node<E>>>) -> Digest {
+    match &node.0 {
+        None => 0.hash(),
+        Some(n) => {
+            let left_hash = hash_node(&n.left);
+            let right_hash = hash_node(&n.right);
+            (n.entry.hashable_pair(), (left_hash, right_hash)).hash()
+        }
+    }
+}
+
 impl<E: ZHashableEntry> Hashable for ZBase<E> {
     fn hash(&self) -> Digest {
-        fn hash_node<E: ZHashableEntry>(node: &Zeroable<Box<Node<E>>>) -> Digest {
-            match &node.0 {
-                None => 0.hash(),
-                Some(n) => {
-                    let left_hash = hash_node(&n.left);
-                    let right_hash = hash_node(&n.right);
-                    (n.entry.hashable_pair(), (left_hash, right_hash)).hash()
-                }
-            }
-        }
         hash_node(&self.root)
     }
 
@@ -246,6 +431,164 @@ impl<E: ZHashableEntry> Hashable for ZBase<E> {
     }
 }
 
+/// Path from the root towards a key, as laid out by `Hashable`: each node is `[pair [left right]]`.
+struct MerkleWalk<'a, E> {
+    /// Axis of the entry's pair, or of the empty subtree where the key would be
+    axis: u64,
+    /// Sibling hashes, from the root down
+    siblings: Vec<Digest>,
+    found: Option<&'a E>,
+    predecessor: Option<&'a E>,
+    successor: Option<&'a E>,
+}
+
+/// Like Hoon's `+peg`, but `None` on overflow.
+fn peg(axis: u64, child: u64) -> Option<u64> {
+    let bits = 63 - child.leading_zeros();
+    if axis.leading_zeros() < bits {
+        return None;
+    }
+    Some((axis << bits) | (child ^ (1 << bits)))
+}
+
+/// Whether `axis` is reached from `from` by repeatedly taking the same child (`0b10` for left,
+/// `0b11` for right) of a treap node.
+fn descends(mut axis: u64, from: u64, turn: u64) -> bool {
+    while axis > from {
+        if axis & 0b11 != turn {
+            return false;
+        }
+        axis >>= 2;
+    }
+    axis == from
+}
+
+impl<E: ZHashableEntry> ZBase<E> {
+    fn merkle_walk<Q: NounEncode + ?Sized>(&self, key: &Q) -> Option<MerkleWalk<'_, E>> {
+        let mut walk = MerkleWalk {
+            axis: 1,
+            siblings: Vec::new(),
+            found: None,
+            predecessor: None,
+            successor: None,
+        };
+        let mut node = &self.root;
+        while let Some(n) = node.as_deref() {
+            if Self::tip_eq(key, n.entry.key()) {
+                walk.siblings
+                    .push((hash_node(&n.left), hash_node(&n.right)).hash());
+                walk.axis = peg(walk.axis, 2)?;
+                walk.found = Some(&n.entry);
+                return Some(walk);
+            }
+            walk.siblings.push(n.entry.hashable_pair().hash());
+            if Self::gor_tip(key, n.entry.key()) {
+                walk.siblings.push(hash_node(&n.right));
+                walk.axis = peg(walk.axis, 6)?;
+                walk.successor = Some(&n.entry);
+                node = &n.left;
+            } else {
+                walk.siblings.push(hash_node(&n.left));
+                walk.axis = peg(walk.axis, 7)?;
+                walk.predecessor = Some(&n.entry);
+                node = &n.right;
+            }
+        }
+        Some(walk)
+    }
+
+    fn proven(root: Digest, walk: &MerkleWalk<'_, E>) -> MerkleProvenAxis {
+        MerkleProvenAxis {
+            proof: MerkleProof {
+                root,
+                path: walk.siblings.iter().rev().copied().collect(),
+            },
+            axis: walk.axis,
+        }
+    }
+
+    /// Prove the entry for `key` against the treap's hash, by walking the treap.
+    ///
+    /// The proof verifies against the entry's hashable pair. Returns `None` if the key is absent,
+    /// or if the treap is too deep for a 64-bit axis.
+    pub fn prove_entry<Q: NounEncode + ?Sized>(&self, key: &Q) -> Option<MerkleProvenAxis>
+    where
+        E::Key: Borrow<Q>,
+    {
+        let walk = self.merkle_walk(key)?;
+        walk.found?;
+        Some(Self::proven(self.hash(), &walk))
+    }
+
+    /// Prove that `key` is absent from the treap. Returns `None` if the key is present, or if the
+    /// treap is too deep for a 64-bit axis.
+    pub fn prove_absent<Q: NounEncode + ?Sized>(
+        &self,
+        key: &Q,
+    ) -> Option<NonMembershipProof<E::Pair>>
+    where
+        E: Clone,
+        E::Key: Borrow<Q>,
+    {
+        let walk = self.merkle_walk(key)?;
+        if walk.found.is_some() {
+            return None;
+        }
+        let root = self.hash();
+        let neighbour = |entry: Option<&E>| match entry {
+            None => Some(None),
+            Some(e) => {
+                let walk = self.merkle_walk(e.key())?;
+                Some(Some((e.clone().into_pair(), Self::proven(root, &walk))))
+            }
+        };
+        Some(NonMembershipProof {
+            empty: Self::proven(root, &walk),
+            predecessor: neighbour(walk.predecessor)?,
+            successor: neighbour(walk.successor)?,
+        })
+    }
+
+    /// Check a [`NonMembershipProof`] for `key` against a committed treap hash. `key_of` picks the
+    /// key out of a neighbour's pair.
+    pub(crate) fn verify_absent<Q: NounEncode + ?Sized, T: Hashable, K: NounEncode + ?Sized>(
+        proof: &NonMembershipProof<T>,
+        root: &Digest,
+        key: &Q,
+        key_of: impl Fn(&T) -> &K,
+    ) -> bool {
+        let empty = &proof.empty;
+        if empty.proof.root != *root || !empty.proof.verify(empty.axis, &()) {
+            return false;
+        }
+
+        // The empty subtree must follow the predecessor's right child, and then only left turns.
+        let predecessor_ok = match &proof.predecessor {
+            None => descends(empty.axis, 1, 0b10),
+            Some((pair, proven)) => {
+                proven.proof.root == *root
+                    && proven.axis.is_multiple_of(2)
+                    && proven.proof.verify(proven.axis, pair)
+                    && Self::tip_lt(key_of(pair), key)
+                    && descends(empty.axis, (proven.axis / 2) * 4 + 3, 0b10)
+            }
+        };
+        // And mirrored for the successor.
+        let successor_ok = match &proof.successor {
+            None => descends(empty.axis, 1, 0b11),
+            Some((pair, proven)) => {
+                proven.proof.root == *root
+                    && proven.axis.is_multiple_of(2)
+                    && proven.proof.verify(proven.axis, pair)
+                    && Self::tip_lt(key, key_of(pair))
+                    && descends(empty.axis, (proven.axis / 2) * 4 + 2, 0b11)
+            }
+        };
+
+        predecessor_ok && successor_ok
+    }
+}
+
 impl<E: ZEntry> NounEncode for ZBase<E> {
     fn to_noun(&self) -> Noun {
         fn visit<E: ZEntry>(node: &Zeroable<Box<Node<E>>>) -> Noun {
@@ -1,7 +1,7 @@
 use core::borrow::Borrow;
 
 use crate::zbase::{ZBase, ZEntry, ZHashableEntry};
-use crate::{Hashable, NounDecode, NounEncode};
+use crate::{Digest, Hashable, MerkleProvenAxis, NonMembershipProof, NounDecode, NounEncode};
 use alloc::fmt::Debug;
 #[cfg(feature = "wasm")]
 use alloc::{boxed::Box, format, string::ToString};
@@ -211,6 +211,69 @@ impl<K: Hashable + NounEncode, V: NounEncode> ZMap<K, V> {
     {
         self.0.get_entry(key).map(|e| e.pair())
     }
+
+    pub fn remove<Q: NounEncode + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.0.remove_entry(key).map(|e| e.value)
+    }
+
+    /// Union of both maps. Values of `other` win on equal keys.
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0.union(other.0))
+    }
+
+    /// Keys present in both maps, with values of `other`.
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0.intersection(other.0))
+    }
+
+    /// Entries of `self` whose keys are not in `other`.
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0.difference(other.0))
+    }
+
+    /// Split into entries ordered before and after `key`, and the value at `key`.
+    pub fn split<Q: NounEncode + ?Sized>(self, key: &Q) -> (Self, Option<V>, Self)
+    where
+        K: Borrow<Q>,
+    {
+        let (left, entry, right) = self.0.split(key);
+        (Self(left), entry.map(|e| e.value), Self(right))
+    }
+}
+
+impl<K: Hashable + NounEncode, V: Hashable + NounEncode> ZMap<K, V> {
+    /// Prove that `key` is in the map. The proof verifies against `(&key, &value)`, with the map's
+    /// hash as root.
+    pub fn prove_key<Q: NounEncode + ?Sized>(&self, key: &Q) -> Option<MerkleProvenAxis>
+    where
+        K: Borrow<Q>,
+    {
+        self.0.prove_entry(key)
+    }
+
+    /// Prove that `key` is not in the map. Returns `None` if it is.
+    pub fn prove_absent_key<Q: NounEncode + ?Sized>(
+        &self,
+        key: &Q,
+    ) -> Option<NonMembershipProof<(K, V)>>
+    where
+        K: Borrow<Q> + Clone,
+        V: Clone,
+    {
+        self.0.prove_absent(key)
+    }
+
+    /// Check a proof from [`ZMap::prove_absent_key`] against a committed map hash.
+    pub fn verify_absent_key<Q: NounEncode + ?Sized>(
+        proof: &NonMembershipProof<(K, V)>,
+        root: &Digest,
+        key: &Q,
+    ) -> bool {
+        ZBase::<ZMapEntry<K, V>>::verify_absent(proof, root, key, |(k, _)| k)
+    }
 }
 
 #[cfg(test)]
@@ -238,4 +301,54 @@ mod tests {
         let zm2: ZMap<String, (u64, u64)> = serde_json::from_str(&json).unwrap();
         assert_eq!(zm, zm2);
     }
+
+    #[test]
+    fn test_zmap_set_ops() {
+        let a: ZMap<u64, u64> = (1..=12).map(|k| (k, 0)).collect();
+        let b: ZMap<u64, u64> = (8..=20).map(|k| (k, 1)).collect();
+
+        let union = a.clone().union(b.clone());
+        let expected: ZMap<u64, u64> = (1..=20).map(|k| (k, (k >= 8) as u64)).collect();
+        assert_eq!(union, expected);
+        assert_eq!(union.hash(), expected.hash());
+
+        let intersection = a.clone().intersection(b.clone());
+        let expected: ZMap<u64, u64> = (8..=12).map(|k| (k, 1)).collect();
+        assert_eq!(intersection, expected);
+        assert_eq!(intersection.hash(), expected.hash());
+
+        let difference = b.clone().difference(a.clone());
+        let expected: ZMap<u64, u64> = (13..=20).map(|k| (k, 1)).collect();
+        assert_eq!(difference, expected);
+        assert_eq!(difference.hash(), expected.hash());
+
+        let (left, value, right) = union.clone().split(&8);
+        assert_eq!(value, Some(1));
+        assert_eq!(left.len() + right.len(), 19);
+
+        let mut removed = union;
+        assert_eq!(removed.remove(&8), Some(1));
+        assert_eq!(removed.remove(&8), None);
+        assert_eq!(removed, left.union(right));
+    }
+
+    #[test]
+    fn test_zmap_prove_key() {
+        let zm: ZMap<u64, u64> = (1..=20).map(|k| (k, k * 100)).collect();
+        let root = zm.hash();
+        for k in 1..=20u64 {
+            let MerkleProvenAxis { proof, axis } = zm.prove_key(&k).unwrap();
+            assert_eq!(proof.root, root);
+            assert!(proof.verify(axis, &(&k, &(k * 100))));
+            assert!(!proof.verify(axis, &(&k, &(k * 100 + 1))));
+        }
+
+        for k in 21..=30u64 {
+            assert!(zm.prove_key(&k).is_none());
+            let proof = zm.prove_absent_key(&k).unwrap();
+            assert!(ZMap::verify_absent_key(&proof, &root, &k));
+            assert!(!ZMap::verify_absent_key(&proof, &root, &1));
+        }
+        assert!(zm.prove_absent_key(&1).is_none());
+    }
 }
@@ -1,8 +1,9 @@
-use super::{Hashable, NounDecode, NounEncode};
+use super::{Digest, Hashable, MerkleProvenAxis, NonMembershipProof, NounDecode, NounEncode};
 use crate::zbase::{ZBase, ZEntry, ZHashableEntry};
 use alloc::fmt::Debug;
 #[cfg(feature = "wasm")]
 use alloc::{boxed::Box, format, string::ToString};
+use core::borrow::Borrow;
 
 #[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, NounDecode, NounEncode, Hashable)]
 #[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
@@ -190,6 +191,62 @@ impl<T: Hashable + NounEncode> ZSet<T> {
     pub fn insert(&mut self, key: T) {
         self.0.insert_entry(ZSetEntry { key });
     }
+
+    pub fn remove<Q: NounEncode + ?Sized>(&mut self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.0.remove_entry(key).is_some()
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0.union(other.0))
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0.intersection(other.0))
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0.difference(other.0))
+    }
+
+    /// Split into elements ordered before and after `key`, and whether `key` was present.
+    pub fn split<Q: NounEncode + ?Sized>(self, key: &Q) -> (Self, bool, Self)
+    where
+        T: Borrow<Q>,
+    {
+        let (left, entry, right) = self.0.split(key);
+        (Self(left), entry.is_some(), Self(right))
+    }
+}
+
+impl<T: Hashable + NounEncode> ZSet<T> {
+    /// Prove that `key` is in the set. The proof verifies against `key`, with the set's hash as
+    /// root.
+    pub fn prove_member<Q: NounEncode + ?Sized>(&self, key: &Q) -> Option<MerkleProvenAxis>
+    where
+        T: Borrow<Q>,
+    {
+        self.0.prove_entry(key)
+    }
+
+    /// Prove that `key` is not in the set. Returns `None` if it is.
+    pub fn prove_non_member<Q: NounEncode + ?Sized>(&self, key: &Q) -> Option<NonMembershipProof<T>>
+    where
+        T: Borrow<Q> + Clone,
+    {
+        self.0.prove_absent(key)
+    }
+
+    /// Check a proof from [`ZSet::prove_non_member`] against a committed set hash.
+    pub fn verify_non_member<Q: NounEncode + ?Sized>(
+        proof: &NonMembershipProof<T>,
+        root: &Digest,
+        key: &Q,
+    ) -> bool {
+        ZBase::<ZSetEntry<T>>::verify_absent(proof, root, key, |k| k)
+    }
 }
 
 #[cfg(test)]
@@ -198,6 +255,12 @@ mod tests {
     use alloc::string::{String, ToString};
     use alloc::vec::Vec;
 
+    // Treaps are canonical for a given key set, so every operation must produce exactly the tree
+    // that inserting the resulting keys would. `EXPECTED_ORDER` is the Hoon-produced shape.
+    const EXPECTED_ORDER: [u64; 20] = [
+        20, 4, 15, 14, 2, 19, 6, 5, 8, 7, 11, 1, 17, 16, 3, 13, 10, 12, 18, 9,
+    ];
+
     #[test]
     fn test_zset_encode_decode() {
         let mut zm = ZSet::<&str>::new();
@@ -220,9 +283,7 @@ mod tests {
 
     #[test]
     fn test_zset_ins_order() {
-        let expected_order = [
-            20, 4, 15, 14, 2, 19, 6, 5, 8, 7, 11, 1, 17, 16, 3, 13, 10, 12, 18, 9,
-        ];
+        let expected_order = EXPECTED_ORDER;
 
         let mut zs = ZSet::<u64>::new();
 
@@ -242,4 +303,112 @@ mod tests {
         assert_eq!(zs.iter().copied().collect::<Vec<_>>(), &expected_order);
         assert_eq!(zs.into_iter().collect::<Vec<_>>(), &expected_order);
     }
+
+    fn set(keys: impl IntoIterator<Item = u64>) -> ZSet<u64> {
+        keys.into_iter().collect()
+    }
+
+    fn assert_same(a: &ZSet<u64>, b: &ZSet<u64>) {
+        assert_eq!(a, b);
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.to_noun(), b.to_noun());
+    }
+
+    #[test]
+    fn test_zset_remove() {
+        let mut zs = set(1..=20);
+        for i in (2..=20).step_by(2) {
+            assert!(zs.remove(&i));
+        }
+        assert!(!zs.remove(&2));
+        assert!(!zs.remove(&21));
+        assert_same(&zs, &set((1..=20).step_by(2)));
+
+        for i in (2..=20).step_by(2) {
+            zs.insert(i);
+        }
+        assert_eq!(zs.iter().copied().collect::<Vec<_>>(), &EXPECTED_ORDER);
+
+        for i in 1..=20 {
+            assert!(zs.remove(&i));
+        }
+        assert!(zs.is_empty());
+    }
+
+    #[test]
+    fn test_zset_union_intersection_difference() {
+        let a = set(1..=12);
+        let b = set(8..=20);
+
+        let union = a.clone().union(b.clone());
+        assert_eq!(union.iter().copied().collect::<Vec<_>>(), &EXPECTED_ORDER);
+        assert_same(&union, &b.clone().union(a.clone()));
+
+        assert_same(&a.clone().intersection(b.clone()), &set(8..=12));
+        assert_same(&b.clone().intersection(a.clone()), &set(8..=12));
+        assert_same(&a.clone().difference(b.clone()), &set(1..=7));
+        assert_same(&b.clone().difference(a.clone()), &set(13..=20));
+
+        assert_same(&a.clone().union(ZSet::new()), &a);
+        assert!(a.clone().intersection(ZSet::new()).is_empty());
+        assert_same(&a.clone().difference(ZSet::new()), &a);
+        assert!(a.clone().difference(a).is_empty());
+    }
+
+    #[test]
+    fn test_zset_split() {
+        let (left, found, right) = set(1..=20).split(&10);
+        assert!(found);
+        assert!(!left.contains(&10) && !right.contains(&10));
+        assert_eq!(left.len() + right.len(), 19);
+        assert!(left.clone().intersection(right.clone()).is_empty());
+
+        let mut joined = left.clone().union(right.clone());
+        joined.insert(10);
+        assert_eq!(joined.iter().copied().collect::<Vec<_>>(), &EXPECTED_ORDER);
+        assert_same(&left.clone(), &set(left.iter().copied()));
+        assert_same(&right.clone(), &set(right.iter().copied()));
+
+        let (left2, found, right2) = set((1..=20).filter(|i| *i != 10)).split(&10);
+        assert!(!found);
+        assert_same(&left, &left2);
+        assert_same(&right, &right2);
+    }
+
+    #[test]
+    fn test_zset_prove_member() {
+        let zs = set(1..=20);
+        let root = zs.hash();
+        for i in 1..=20u64 {
+            let MerkleProvenAxis { proof, axis } = zs.prove_member(&i).unwrap();
+            assert_eq!(proof.root, root);
+            assert!(proof.verify(axis, &i));
+            assert!(!proof.verify(axis, &(i + 1)));
+        }
+        assert!(zs.prove_member(&21).is_none());
+        assert!(ZSet::<u64>::new().prove_member(&1).is_none());
+    }
+
+    #[test]
+    fn test_zset_prove_non_member() {
+        let zs = set((2..=40).step_by(2));
+        let root = zs.hash();
+        for i in (1..=41).step_by(2) {
+            let proof = zs.prove_non_member(&i).unwrap();
+            assert!(ZSet::verify_non_member(&proof, &root, &i));
+            assert!(!ZSet::verify_non_member(&proof, &set([1]).hash(), &i));
+            for j in (2..=40).step_by(2) {
+                assert!(zs.prove_non_member(&j).is_none());
+                assert!(!ZSet::verify_non_member(&proof, &root, &j));
+            }
+
+            let decoded = NonMembershipProof::<u64>::from_noun(&proof.to_noun()).unwrap();
+            assert!(ZSet::verify_non_member(&decoded, &root, &i));
+        }
+
+        let empty = ZSet::<u64>::new();
+        let proof = empty.prove_non_member(&1).unwrap();
+        assert!(proof.predecessor.is_none() && proof.successor.is_none());
+        assert!(ZSet::verify_non_member(&proof, &empty.hash(), &1));
+    }
 }